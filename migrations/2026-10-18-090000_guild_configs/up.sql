@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE guild_configs (
+  id SERIAL PRIMARY KEY,
+  guild_id BIGINT NOT NULL,
+  name VARCHAR NOT NULL,
+  value VARCHAR NOT NULL,
+  UNIQUE (guild_id, name)
+)
\ No newline at end of file
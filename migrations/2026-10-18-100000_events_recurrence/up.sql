@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE events ADD COLUMN recurrence VARCHAR;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE events ADD COLUMN delivery VARCHAR NOT NULL DEFAULT 'channel';
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE events DROP COLUMN project_id;
+ALTER TABLE projects DROP COLUMN deadline_date;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE projects ADD COLUMN deadline_date DATE;
+ALTER TABLE events ADD COLUMN project_id INTEGER REFERENCES projects (id) ON DELETE CASCADE;
\ No newline at end of file
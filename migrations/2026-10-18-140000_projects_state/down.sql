@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE projects DROP COLUMN state;
\ No newline at end of file
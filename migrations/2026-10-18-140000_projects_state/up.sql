@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE projects ADD COLUMN state VARCHAR NOT NULL DEFAULT 'active';
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE projects DROP COLUMN thread_ids;
+ALTER TABLE projects DROP COLUMN voice_channel_id;
+ALTER TABLE projects DROP COLUMN role_id;
+DROP TABLE project_templates;
\ No newline at end of file
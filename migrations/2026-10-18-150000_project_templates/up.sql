@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE project_templates (
+  id SERIAL PRIMARY KEY,
+  guild_id BIGINT NOT NULL,
+  name VARCHAR NOT NULL,
+  voice_channel BOOLEAN NOT NULL DEFAULT FALSE,
+  threads VARCHAR[] NOT NULL DEFAULT '{}',
+  UNIQUE (guild_id, name)
+);
+ALTER TABLE projects ADD COLUMN role_id BIGINT;
+ALTER TABLE projects ADD COLUMN voice_channel_id BIGINT;
+ALTER TABLE projects ADD COLUMN thread_ids BIGINT[] NOT NULL DEFAULT '{}';
\ No newline at end of file
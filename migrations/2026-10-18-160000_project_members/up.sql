@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE project_members (
+  id SERIAL PRIMARY KEY,
+  project_id INTEGER NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+  user_id BIGINT NOT NULL,
+  joined_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+  UNIQUE (project_id, user_id)
+);
\ No newline at end of file
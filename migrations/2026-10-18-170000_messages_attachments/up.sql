@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE messages ADD COLUMN attachments VARCHAR[] NOT NULL DEFAULT '{}';
\ No newline at end of file
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE archive_log (
+  id SERIAL PRIMARY KEY,
+  guild_id BIGINT NOT NULL,
+  channel_id BIGINT NOT NULL,
+  previous_category BIGINT,
+  previous_position INTEGER NOT NULL,
+  archived_by BIGINT,
+  archived_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'utc'),
+  restored_by BIGINT,
+  restored_at TIMESTAMP
+);
\ No newline at end of file
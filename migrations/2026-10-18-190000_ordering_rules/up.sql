@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE ordering_rules (
+  id SERIAL PRIMARY KEY,
+  guild_id BIGINT NOT NULL,
+  category_id BIGINT,
+  sort VARCHAR NOT NULL,
+  pinned BIGINT[] NOT NULL DEFAULT '{}',
+  UNIQUE (guild_id, category_id)
+);
\ No newline at end of file
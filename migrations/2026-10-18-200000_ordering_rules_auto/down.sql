@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE ordering_rules DROP COLUMN auto;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE ordering_rules ADD COLUMN auto BOOLEAN NOT NULL DEFAULT false;
\ No newline at end of file
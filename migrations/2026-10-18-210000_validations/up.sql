@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE validations (
+  id SERIAL PRIMARY KEY,
+  guild_id BIGINT NOT NULL,
+  channel_id BIGINT NOT NULL,
+  message_id BIGINT NOT NULL UNIQUE,
+  author BIGINT NOT NULL,
+  action VARCHAR NOT NULL,
+  required_role BIGINT,
+  quorum INTEGER NOT NULL DEFAULT 1,
+  approvals BIGINT[] NOT NULL DEFAULT '{}',
+  expires_at TIMESTAMP NOT NULL
+);
\ No newline at end of file
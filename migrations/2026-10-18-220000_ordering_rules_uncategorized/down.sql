@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX ordering_rules_uncategorized;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- UNIQUE (guild_id, category_id) lets several NULL categories through, the latest rule is kept
+DELETE FROM ordering_rules older USING ordering_rules newer
+WHERE older.guild_id = newer.guild_id
+  AND older.category_id IS NULL
+  AND newer.category_id IS NULL
+  AND older.id < newer.id;
+CREATE UNIQUE INDEX ordering_rules_uncategorized ON ordering_rules (guild_id) WHERE category_id IS NULL;
\ No newline at end of file
@@ -13,8 +13,18 @@ pub(crate) mod util;
 use structures::*;
 use util::*;
 
+/// Box the future of an async function so it can be stored as a callback.
+///
+/// With options the function is also registered as a bot command:
+/// ```rust,ignore
+/// #[command(name = "remindme", aliases = ["remind"], usage = "@BOT remindme <WHEN> <CONTENT>", min_args = 2, max_args = 2, permission = User)]
+/// ```
+/// `usage` defaults to `@BOT <name>`, `min_args` to 0, `max_args` to `min_args`,
+/// `permission` to `User` and `channel` (a channel id) to any channel. Only the commands
+/// with `direct_message = true` can be run in a direct message with the bot.
 #[proc_macro_attribute]
-pub fn command(_attr: TokenStream, input: TokenStream) -> TokenStream {
+pub fn command(attr: TokenStream, input: TokenStream) -> TokenStream {
+  let options = parse_macro_input!(attr as CommandOptions);
   let command = parse_macro_input!(input as Command);
 
   match command {
@@ -27,6 +37,11 @@ pub fn command(_attr: TokenStream, input: TokenStream) -> TokenStream {
 
       populate_fut_lifetimes_on_refs(&mut fun.args);
       let args = fun.args;
+      let registration = if options.is_registered() {
+        options.registration(&fun_name)
+      } else {
+        quote! {}
+      };
 
       (quote! {
           #(#cooked)*
@@ -36,10 +51,21 @@ pub fn command(_attr: TokenStream, input: TokenStream) -> TokenStream {
 
               async move { #(#body)* }.boxed()
           }
+
+          #registration
       })
       .into()
     }
     Command::Closure(closure) => {
+      if options.is_registered() {
+        return syn::Error::new(
+          proc_macro2::Span::call_site(),
+          "only functions can be registered as commands",
+        )
+        .to_compile_error()
+        .into();
+      }
+
       let cooked = closure.cooked;
       let args = closure.args;
       let ret = closure.ret;
@@ -7,8 +7,8 @@ use syn::{
   parse::{Error, Parse, ParseStream, Result},
   punctuated::Punctuated,
   spanned::Spanned,
-  Attribute, Block, Expr, ExprClosure, FnArg, Ident, Pat, ReturnType, Stmt, Token, Type,
-  Visibility,
+  Attribute, Block, Expr, ExprClosure, FnArg, Ident, Lit, LitBool, LitInt, LitStr, Pat, ReturnType,
+  Stmt, Token, Type, Visibility,
 };
 
 use crate::util::{self, Argument, Parenthesised};
@@ -278,3 +278,168 @@ fn parse_closure_command(
     body: closure.body,
   })
 }
+
+/// A single `key = value` given to `#[command(...)]`.
+struct CommandOption {
+  key: Ident,
+  value: Expr,
+}
+
+impl Parse for CommandOption {
+  fn parse(input: ParseStream<'_>) -> Result<Self> {
+    let key = input.parse()?;
+    input.parse::<Token![=]>()?;
+    let value = input.parse()?;
+    Ok(CommandOption { key, value })
+  }
+}
+
+/// Definition of a command registered with `#[command(name = "...", ...)]`.
+#[derive(Debug, Default)]
+pub struct CommandOptions {
+  pub name: Option<LitStr>,
+  pub aliases: Vec<LitStr>,
+  pub usage: Option<LitStr>,
+  pub min_args: Option<LitInt>,
+  pub max_args: Option<LitInt>,
+  pub permission: Option<Ident>,
+  pub channel: Option<LitInt>,
+  pub direct_message: Option<LitBool>,
+}
+
+fn expect_str(value: Expr) -> Result<LitStr> {
+  match value {
+    Expr::Lit(syn::ExprLit {
+      lit: Lit::Str(lit), ..
+    }) => Ok(lit),
+    _ => Err(Error::new(value.span(), "expected a string literal")),
+  }
+}
+
+fn expect_int(value: Expr) -> Result<LitInt> {
+  match value {
+    Expr::Lit(syn::ExprLit {
+      lit: Lit::Int(lit), ..
+    }) => Ok(lit),
+    _ => Err(Error::new(value.span(), "expected an integer literal")),
+  }
+}
+
+fn expect_bool(value: Expr) -> Result<LitBool> {
+  match value {
+    Expr::Lit(syn::ExprLit {
+      lit: Lit::Bool(lit),
+      ..
+    }) => Ok(lit),
+    _ => Err(Error::new(value.span(), "expected a boolean literal")),
+  }
+}
+
+impl Parse for CommandOptions {
+  fn parse(input: ParseStream<'_>) -> Result<Self> {
+    let mut options = CommandOptions::default();
+    let parsed = Punctuated::<CommandOption, Token![,]>::parse_terminated(input)?;
+
+    for CommandOption { key, value } in parsed {
+      match &*key.to_string() {
+        "name" => options.name = Some(expect_str(value)?),
+        "usage" => options.usage = Some(expect_str(value)?),
+        "min_args" => options.min_args = Some(expect_int(value)?),
+        "max_args" => options.max_args = Some(expect_int(value)?),
+        "channel" => options.channel = Some(expect_int(value)?),
+        "direct_message" => options.direct_message = Some(expect_bool(value)?),
+        "permission" => match value {
+          Expr::Path(path) if path.path.get_ident().is_some() => {
+            options.permission = path.path.get_ident().cloned()
+          }
+          _ => {
+            return Err(Error::new(
+              value.span(),
+              "expected a role: Guest, User, Admin...",
+            ))
+          }
+        },
+        "aliases" => match value {
+          Expr::Array(array) => {
+            options.aliases = array
+              .elems
+              .into_iter()
+              .map(expect_str)
+              .collect::<Result<Vec<_>>>()?
+          }
+          _ => return Err(Error::new(value.span(), "expected a list of aliases")),
+        },
+        _ => {
+          return Err(Error::new(
+            key.span(),
+            format_args!("unknown command option: {}", key),
+          ))
+        }
+      }
+    }
+    let has_options = !options.aliases.is_empty()
+      || options.usage.is_some()
+      || options.min_args.is_some()
+      || options.max_args.is_some()
+      || options.permission.is_some()
+      || options.channel.is_some()
+      || options.direct_message.is_some();
+    if options.name.is_none() && has_options {
+      return Err(input.error("a command needs a `name` to be registered"));
+    }
+    Ok(options)
+  }
+}
+
+impl CommandOptions {
+  /// Without any option the function is only wrapped, it isn't registered as a command.
+  pub fn is_registered(&self) -> bool {
+    self.name.is_some()
+  }
+
+  /// `inventory` registration of the command, `exec` being the wrapped function.
+  pub fn registration(&self, exec: &Ident) -> TokenStream2 {
+    let name = self.name.as_ref().unwrap();
+    let aliases = &self.aliases;
+    let usage = match &self.usage {
+      Some(usage) => quote! { #usage },
+      None => quote! { concat!("@BOT ", #name) },
+    };
+    let min_args = match &self.min_args {
+      Some(min_args) => quote! { #min_args },
+      None => quote! { 0 },
+    };
+    let max_args = match &self.max_args {
+      Some(max_args) => quote! { #max_args },
+      None => min_args.clone(),
+    };
+    let permission = match &self.permission {
+      Some(permission) => quote! { #permission },
+      None => quote! { User },
+    };
+    let channel = match &self.channel {
+      Some(channel) => quote! { Some(::serenity::model::id::ChannelId(#channel)) },
+      None => quote! { None },
+    };
+    let direct_message = match &self.direct_message {
+      Some(direct_message) => quote! { #direct_message },
+      None => quote! { false },
+    };
+
+    quote! {
+      ::inventory::submit! {
+        crate::core::commands::Command {
+          name: #name,
+          aliases: &[#(#aliases),*],
+          exec: #exec,
+          argument_min: #min_args,
+          argument_max: #max_args,
+          channel: #channel,
+          direct_message: #direct_message,
+          usage: #usage,
+          permission: crate::database::Role::#permission,
+        }
+      }
+    }
+  }
+}
@@ -1,16 +0,0 @@
-#[allow(dead_code)]
-pub mod discordids;
-
-pub const NUMBERS: [&str; 11] = [
-  "0\u{fe0f}\u{20e3}",
-  "1\u{fe0f}\u{20e3}",
-  "2\u{fe0f}\u{20e3}",
-  "3\u{fe0f}\u{20e3}",
-  "4\u{fe0f}\u{20e3}",
-  "5\u{fe0f}\u{20e3}",
-  "6\u{fe0f}\u{20e3}",
-  "7\u{fe0f}\u{20e3}",
-  "8\u{fe0f}\u{20e3}",
-  "9\u{fe0f}\u{20e3}",
-  "🔟",
-];
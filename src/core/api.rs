@@ -1,11 +1,15 @@
 use crate::{
-  constants::discordids::DEVOPS_CHANNEL,
-  core::parse,
+  core::{
+    config::{self, ConfigKey},
+    parse,
+    timezone::DEFAULT_TIMEZONE,
+  },
   database::{self, Message},
+  features::project_manager::export,
 };
 use parse::DiscordIds;
 use rocket::{
-  http::{Method, Status},
+  http::{ContentType, Method, Status},
   request::{FromRequest, Outcome, Request},
   serde::json::Json,
   State,
@@ -97,19 +101,15 @@ pub struct Incident {
 
 #[post("/webhook/gcp_alert", format = "json", data = "<alert>")]
 async fn webhook_from_gcp(alert: Json<GCPAlert>, ctx: &State<Context>) -> String {
-  ChannelId(DEVOPS_CHANNEL)
-    .say(
-      &ctx.http,
-      format!(
-        "{}: {}\n{}\n```json\n{:#?}\n```",
-        alert.0.incident.scoping_project_id,
-        alert.0.incident.summary,
-        alert.0.incident.url,
-        alert.0
-      ),
-    )
-    .await
-    .unwrap();
+  let content = format!(
+    "{}: {}\n{}\n```json\n{:#?}\n```",
+    alert.0.incident.scoping_project_id, alert.0.incident.summary, alert.0.incident.url, alert.0
+  );
+  for (_, devops_channel) in config::configured(ConfigKey::DevopsChannel) {
+    if let Err(why) = ChannelId(devops_channel).say(&ctx.http, &content).await {
+      error!("Unable to send the alert to {}: {}", devops_channel, why);
+    }
+  }
   String::from("")
 }
 
@@ -138,6 +138,60 @@ async fn get_channel_message(
   }
 }
 
+/// `format` is `json` (default) or `markdown`
+#[get("/project/<channelid>/export?<format>")]
+async fn get_project_export(
+  channelid: &str,
+  format: Option<&str>,
+  _apikey: ApiKey<'_>,
+  ctx: &State<Context>,
+) -> (Status, (ContentType, String)) {
+  let id = match parse::discord_str_to_id(channelid, Some(DiscordIds::Channel)) {
+    Ok((id, _)) => id,
+    Err(_) => {
+      return (
+        Status::BadRequest,
+        (
+          ContentType::Plain,
+          format!("Unable to parse channelid: {}", channelid),
+        ),
+      )
+    }
+  };
+  let project_export = match export::build_export(id, &ctx.cache) {
+    Some(project_export) => project_export,
+    None => {
+      return (
+        Status::NotFound,
+        (ContentType::Plain, format!("{} isn't a project", channelid)),
+      )
+    }
+  };
+  match format {
+    Some("markdown") => (
+      Status::Ok,
+      (
+        ContentType::Markdown,
+        export::to_markdown(&project_export, DEFAULT_TIMEZONE),
+      ),
+    ),
+    Some("json") | None => match serde_json::to_string(&project_export) {
+      Ok(json) => (Status::Ok, (ContentType::JSON, json)),
+      Err(why) => (
+        Status::InternalServerError,
+        (ContentType::Plain, why.to_string()),
+      ),
+    },
+    Some(format) => (
+      Status::BadRequest,
+      (
+        ContentType::Plain,
+        format!("Unknown format {}, expected json or markdown", format),
+      ),
+    ),
+  }
+}
+
 pub async fn run(ctx: Context) {
   const ADDRESS: &str = "0.0.0.0";
   const PORT: u32 = 8080;
@@ -164,7 +218,12 @@ pub async fn run(ctx: Context) {
     .mount("/", routes![index])
     .mount(
       "/auth",
-      routes![send_message, get_channel_message, webhook_from_gcp],
+      routes![
+        send_message,
+        get_channel_message,
+        get_project_export,
+        webhook_from_gcp
+      ],
     )
     .attach(cors.to_cors().unwrap())
     .launch()
@@ -0,0 +1,297 @@
+//! Typed extraction of the arguments given to a command.
+//!
+//! ```rust,ignore
+//! let mut arguments = params.arguments();
+//! let user: UserId = arguments.next()?;
+//! let reason: Option<String> = arguments.optional()?;
+//! let channels: Vec<ChannelId> = arguments.many()?;
+//! ```
+//! Errors are returned as an [`ArgumentError`] which is replied to the user along with the usage of the command.
+use super::commands::CallBackParams;
+use super::parse::{self, DiscordIds};
+use crate::database::Role;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use serenity::model::id::{ChannelId, MessageId, RoleId, UserId};
+use std::{collections::HashMap, error::Error, fmt::Display, str::FromStr};
+
+#[derive(Debug)]
+pub struct ArgumentError {
+  /// Position of the argument, the command name being 0
+  pub position: usize,
+  pub value: Option<String>,
+  pub reason: String,
+}
+
+impl Display for ArgumentError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self.value {
+      Some(value) => write!(
+        f,
+        "Invalid argument {} `{}`: {}",
+        self.position, value, self.reason
+      ),
+      None => write!(f, "Missing argument {}: {}", self.position, self.reason),
+    }
+  }
+}
+
+impl Error for ArgumentError {}
+
+/// Conversion of a single argument, `EXPECTED` describes the argument when it's missing.
+pub trait FromArgument: Sized {
+  const EXPECTED: &'static str;
+
+  fn from_argument(argument: &str) -> Result<Self, String>;
+}
+
+impl FromArgument for String {
+  const EXPECTED: &'static str = "expected a text";
+
+  /// Quotes were already removed by [`parse::split_message_args`]
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    Ok(argument.to_string())
+  }
+}
+
+impl FromArgument for i64 {
+  const EXPECTED: &'static str = "expected a number";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    argument
+      .parse()
+      .map_err(|_| String::from("this isn't a number"))
+  }
+}
+
+impl FromArgument for i32 {
+  const EXPECTED: &'static str = "expected a number";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    argument
+      .parse()
+      .map_err(|_| String::from("this isn't a number"))
+  }
+}
+
+impl FromArgument for u32 {
+  const EXPECTED: &'static str = "expected a positive number";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    argument
+      .parse()
+      .map_err(|_| String::from("this isn't a positive number"))
+  }
+}
+
+impl FromArgument for Role {
+  const EXPECTED: &'static str = "expected a role: Guest, User, Moderator, Admin";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    Role::from_str(argument).map_err(|_| String::from(Self::EXPECTED))
+  }
+}
+
+macro_rules! discord_id_argument {
+  ($type:ident, $discord_type:expr, $expected:expr) => {
+    impl FromArgument for $type {
+      const EXPECTED: &'static str = $expected;
+
+      fn from_argument(argument: &str) -> Result<Self, String> {
+        parse::discord_str_to_id(argument, Some($discord_type)).map(|(id, _)| $type(id))
+      }
+    }
+  };
+}
+
+discord_id_argument!(UserId, DiscordIds::User, "expected a user: <@user>");
+discord_id_argument!(
+  ChannelId,
+  DiscordIds::Channel,
+  "expected a channel: <#channel>"
+);
+discord_id_argument!(RoleId, DiscordIds::Role, "expected a role: <@&role>");
+discord_id_argument!(MessageId, DiscordIds::Message, "expected a message id");
+
+impl FromArgument for Vec<ChannelId> {
+  const EXPECTED: &'static str = "expected channels: <#channel1>,<#channel2>";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    argument
+      .split(',')
+      .filter(|channel| !channel.is_empty())
+      .map(ChannelId::from_argument)
+      .collect()
+  }
+}
+
+impl FromArgument for Duration {
+  const EXPECTED: &'static str = "expected a duration: 10m, 2h, 1h30m, 5d...";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    parse::duration_from_str(argument).ok_or_else(|| String::from(Self::EXPECTED))
+  }
+}
+
+impl FromArgument for NaiveDate {
+  const EXPECTED: &'static str = "expected a date: dd/mm/yyyy";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    NaiveDate::parse_from_str(argument, "%d/%m/%Y")
+      .or_else(|_| NaiveDate::parse_from_str(argument, "%Y-%m-%d"))
+      .or_else(|_| {
+        NaiveDate::parse_from_str(&format!("{}/{}", argument, Utc::now().year()), "%d/%m/%Y")
+      })
+      .map_err(|_| String::from(Self::EXPECTED))
+  }
+}
+
+/// Cursor over the arguments of a command, the command name is skipped.
+pub struct Arguments<'a> {
+  args: &'a [String],
+  position: usize,
+}
+
+impl<'a> Arguments<'a> {
+  pub fn new(args: &'a [String]) -> Self {
+    Arguments { args, position: 1 }
+  }
+
+  fn error(&self, reason: String) -> ArgumentError {
+    ArgumentError {
+      position: self.position,
+      value: self.args.get(self.position).cloned(),
+      reason,
+    }
+  }
+
+  /// Number of arguments not consumed yet.
+  pub fn remaining(&self) -> usize {
+    self.args.len().saturating_sub(self.position)
+  }
+
+  /// The next argument, without consuming it.
+  pub fn peek(&self) -> Option<&'a str> {
+    self.args.get(self.position).map(String::as_str)
+  }
+
+  /// Consume the next argument as it was written.
+  pub fn next_raw(&mut self) -> Result<&'a str, ArgumentError> {
+    match self.args.get(self.position) {
+      Some(argument) => {
+        self.position += 1;
+        Ok(argument)
+      }
+      None => Err(self.error(String::from("expected an argument"))),
+    }
+  }
+
+  /// Consume the next argument, it's an error if it's missing or invalid.
+  pub fn next<T: FromArgument>(&mut self) -> Result<T, ArgumentError> {
+    match self.args.get(self.position) {
+      Some(argument) => {
+        let value = T::from_argument(argument).map_err(|reason| self.error(reason))?;
+        self.position += 1;
+        Ok(value)
+      }
+      None => Err(self.error(String::from(T::EXPECTED))),
+    }
+  }
+
+  /// Consume the next argument if there is one, it's still an error if it's invalid.
+  pub fn optional<T: FromArgument>(&mut self) -> Result<Option<T>, ArgumentError> {
+    if self.remaining() == 0 {
+      return Ok(None);
+    }
+    self.next().map(Some)
+  }
+
+  /// Consume all the remaining arguments as they were written.
+  pub fn rest(&mut self) -> &'a [String] {
+    let rest = self.args.get(self.position..).unwrap_or_default();
+    self.position += rest.len();
+    rest
+  }
+
+  /// Consume all the remaining arguments, the error is at the first invalid one.
+  pub fn many<T: FromArgument>(&mut self) -> Result<Vec<T>, ArgumentError> {
+    let mut values = Vec::new();
+    while self.remaining() > 0 {
+      values.push(self.next()?);
+    }
+    Ok(values)
+  }
+
+  /// Consume all the remaining arguments as `key=value`, only the keys in `allowed` are accepted.
+  pub fn key_values(
+    &mut self,
+    allowed: &[&str],
+  ) -> Result<HashMap<&'a str, &'a str>, ArgumentError> {
+    let mut values = HashMap::new();
+    while self.remaining() > 0 {
+      let argument = &self.args[self.position];
+      match argument.split_once('=') {
+        Some((key, value)) if allowed.contains(&key) => {
+          values.insert(key, value);
+        }
+        Some((key, _)) => {
+          return Err(self.error(format!(
+            "unknown key {}, expected one of: {}",
+            key,
+            allowed.join(", ")
+          )))
+        }
+        None => return Err(self.error(String::from("expected key=value"))),
+      }
+      self.position += 1;
+    }
+    Ok(values)
+  }
+}
+
+impl<'a> CallBackParams<'a> {
+  pub fn arguments(&self) -> Arguments<'a> {
+    Arguments::new(self.args)
+  }
+}
+
+#[test]
+fn test_arguments() {
+  let args: Vec<String> = vec!["cmd", "<@123456789012345678>", "10m", "text", "a=1", "b=2"]
+    .into_iter()
+    .map(String::from)
+    .collect();
+  let mut arguments = Arguments::new(&args);
+
+  assert_eq!(
+    arguments.next::<UserId>().unwrap(),
+    UserId(123456789012345678)
+  );
+  assert_eq!(arguments.next::<Duration>().unwrap(), Duration::minutes(10));
+  let error = arguments.next::<RoleId>().unwrap_err();
+  assert_eq!(error.position, 3);
+  assert_eq!(arguments.optional::<String>().unwrap().unwrap(), "text");
+  assert!(arguments.key_values(&["a"]).is_err());
+  let values = arguments.key_values(&["a", "b"]).unwrap();
+  assert_eq!(values["b"], "2");
+  assert!(arguments.optional::<String>().unwrap().is_none());
+  assert_eq!(arguments.next::<String>().unwrap_err().value, None);
+  assert!(arguments.rest().is_empty());
+
+  let mut arguments = Arguments::new(&args);
+  arguments.next::<UserId>().unwrap();
+  assert_eq!(arguments.rest(), &args[2..]);
+  assert_eq!(arguments.remaining(), 0);
+  let mut arguments = Arguments::new(&args[..3]);
+  assert_eq!(arguments.many::<UserId>().unwrap_err().position, 2);
+  let mut arguments = Arguments::new(&args[1..3]);
+  assert_eq!(
+    arguments.many::<Duration>().unwrap(),
+    vec![Duration::minutes(10)]
+  );
+
+  assert_eq!(
+    Vec::<ChannelId>::from_argument("<#123456789012345678>,<#223456789012345678>").unwrap(),
+    vec![ChannelId(123456789012345678), ChannelId(223456789012345678)]
+  );
+  assert!(Vec::<ChannelId>::from_argument("<#123456789012345678>,general").is_err());
+}
@@ -1,21 +1,15 @@
 //! Handle the connection with discord and it's events.
-use std::{collections::HashMap, error::Error, fmt::Write, process, str::FromStr};
+use std::{collections::HashMap, error::Error, fmt::Write, process};
 
-use super::{parse, slash_command};
-use crate::features::anyone::anyone;
-use crate::features::calendar::check_calendar;
-use crate::features::{
-  archivage, emoji, funny, invite_action, ordering, project_manager, renaming,
-};
-use crate::{
-  database::{NewStorage, Role, StorageDataType, INSTANCE},
-  features::events,
-};
+use crate::database::{NewStorage, Role, StorageDataType, INSTANCE};
 use procedural_macros::command;
-use serenity::{futures::future::BoxFuture, FutureExt};
+use serenity::futures::future::BoxFuture;
 use serenity::{
   model::channel::Message,
-  model::{gateway::Activity, id::ChannelId},
+  model::{
+    gateway::Activity,
+    id::{ChannelId, MessageId, UserId},
+  },
   prelude::*,
 };
 
@@ -29,15 +23,23 @@ pub type CallbackReturn<'fut> =
 type Callback = fn(CallBackParams) -> CallbackReturn;
 
 /// Struct that old Traits Implementations to Handle the different events send by discord.
+///
+/// Commands are declared with `#[command(name = "...", ...)]` on their callback.
 pub struct Command {
+  pub name: &'static str,
+  pub aliases: &'static [&'static str],
   pub exec: Callback,
   pub argument_min: usize,
   pub argument_max: usize,
   pub channel: Option<ChannelId>,
+  /// Can be run in a direct message with the bot
+  pub direct_message: bool,
   pub usage: &'static str,
   pub permission: Role,
 }
 
+inventory::collect!(Command);
+
 const INTRODUCE: &str = "Hello, i am a BOT. i was designed to peek over you conversations and make very weird comments. i don't have any purpose yet, but you can ask me about the weather";
 const MOM_RFC: &str = "```\
 - It must be an insult or a degrading comment
@@ -77,265 +79,49 @@ lazy_static! {
     "retard" => '⌚',
     "pm" => '🐱'
   ];
-  pub static ref COMMANDS_LIST: HashMap<&'static str, Command> = hashmap![
-    "quit" =>
-    Command {
-      exec: |_| -> CallbackReturn { process::exit(0x0100) },
-      argument_min: 0,
-      argument_max: 0,
-      channel: None,
-      usage: "@BOT quit",
-      permission: Role::Admin,
-    },
-    "send_message" =>
-    Command {
-      exec: manual_send_message,
-      argument_min: 2,
-      argument_max: 2,
-      channel: None,
-      usage: "@BOT send_message <#channelid> <@who>",
-      permission: Role::Admin,
-    },
-    "users" =>
-    Command {
-      exec: |_| -> CallbackReturn { async move { Ok(Some(format!("{:?}", INSTANCE.write().unwrap().users))) }.boxed() },
-      argument_min: 0,
-      argument_max: 0,
-      channel: None,
-      usage: "@BOT users",
-      permission: Role::Admin,
-    },
-    "slash-command-set" =>
-    Command {
-      exec: slash_command::set,
-      argument_min: 0,
-      argument_max: 0,
-      channel: None,
-      usage: "@BOT quit",
-      permission: Role::Admin,
-    },
-    "promote" =>
-    Command {
-      exec: promote_user,
-      argument_min: 2,
-      argument_max: 2,
-      channel: None,
-      usage: "@BOT promote <@user> <role>",
-      permission: Role::Admin,
-    },
-    "set-activity" =>
-    Command {
-      exec: set_activity,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT set-activity <ACTIVITY_NAME>",
-      permission: Role::User,
-    },
-    "edit" =>
-    Command {
-      exec: modify_message,
-      argument_min: 2,
-      argument_max: 3,
-      channel: None,
-      usage: "@BOT edit [<#channel>] <message_id> \"<new content>\"",
-      permission: Role::User,
-    },
-    "create-project" =>
-    Command {
-      exec: project_manager::create,
-      argument_min: 1,
-      argument_max: 7,
-      channel: None,
-      usage: "@BOT create-project <name> [codex=<codex>, client=<client>, lead=<Lead>, deadline=<Deadline>, description=<Brief projet>, contexte=<Contexte>]",
-      permission: Role::User,
-    },
-    "add-project" =>
-    Command {
-      exec: project_manager::add,
-      argument_min: 2,
-      argument_max: 8,
-      channel: None,
-      usage: "@BOT add-project <#channel_id> <name> [codex=<codex>, client=<client>, lead=<Lead>, deadline=<Deadline>, description=<Brief projet>, contexte=<Contexte>]",
-      permission: Role::User,
-    },
-    "delete-project" =>
-    Command {
-      exec: project_manager::delete,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT delete-project <name>",
-      permission: Role::User,
-    },
-    "add" =>
-    Command {
-      exec: project_manager::add_user,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT add <@user>",
-      permission: Role::User,
-    },
-    "remove" =>
-    Command {
-      exec: project_manager::remove_user,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT remove <@user>",
-      permission: Role::User,
-    },
-    "project-clear-user" =>
-    Command {
-    exec: project_manager::remove_user_from_all,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT project-clear-user <User>",
-      permission: Role::Admin,
-    },
-    "invite" =>
-    Command {
-      exec: invite_action::create,
-      argument_min: 2,
-      argument_max: 3,
-      channel: None,
-      usage: "@BOT invite [<#invitecode>] <role AND OR channel>",
-      permission: Role::User,
-    },
-    "archivage" =>
-    Command {
-      exec: archivage::archive_channels_command,
-      argument_min: 0,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT archivage [<category>]",
-      permission: Role::Admin,
-    },
-    "ordering" =>
-    Command {
-      exec: ordering::ordering_channel_command,
-      argument_min: 0,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT ordering [<category>]",
-      permission: Role::Admin,
-    },
-    "remindme" =>
-    Command {
-      exec: events::remind_me,
-      argument_min: 2,
-      argument_max: 2,
-      channel: None,
-      usage: "@BOT remindme <WHEN ex: 1minute,1m,10h,5days> <CONTENT>",
-      permission: Role::User,
-    },
-    "attack" =>
-    Command {
-      exec: funny::attack_lauch,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT attack <@user>",
-      permission: Role::User,
-    },
-    "mom-change" =>
-    Command {
-      exec: funny::mom_change,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT momchange <@user>",
-      permission: Role::User,
-    },
-    "mom" =>
-    Command {
-      exec: funny::which_mom,
-      argument_min: 0,
-      argument_max: 0,
-      channel: None,
-      usage: "@BOT mom",
-      permission: Role::User,
-    },
-    "cat" =>
-    Command {
-      exec: funny::get_cat_pic,
-      argument_min: 0,
-      argument_max: 0,
-      channel: None,
-      usage: "@BOT cat",
-      permission: Role::Guest,
-    },
-    "rename" =>
-    Command {
-      exec: renaming::rename,
-      argument_min: 2,
-      argument_max: 3,
-      channel: None,
-      usage: "@BOT rename <@user> <new nickname> [<guild>]",
-      permission: Role::User,
-    },
-    "emoji-add" =>
-    Command {
-      exec: emoji::add,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT emoji-add <custom emoji>",
-      permission: Role::User,
-    },
-    "block" =>
-    Command {
-      exec: block_user,
-      argument_min: 1,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT block <user>",
-      permission: Role::Admin,
-    },
-    "help" =>
-    Command {
-      exec: print_help,
-      argument_min: 0,
-      argument_max: 0,
-      channel: None,
-      usage: "@BOT help",
-      permission: Role::Guest,
-    },
-    "anyone" =>
-    Command {
-      exec: anyone,
-      argument_min: 0,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT anyone <message>",
-      permission: Role::User,
-    },
-    "check-calendar" =>
-    Command {
-      exec: check_calendar,
-      argument_min: 0,
-      argument_max: 1,
-      channel: None,
-      usage: "@BOT check_calendar <date = MM/AAAA>",
-      permission: Role::User,
+  /// Every command registered with `#[command(...)]`, by name and aliases.
+  pub static ref COMMANDS_LIST: HashMap<&'static str, &'static Command> = {
+    let mut commands = HashMap::new();
+    for command in inventory::iter::<Command> {
+      commands.insert(command.name, command);
+      for alias in command.aliases {
+        commands.insert(*alias, command);
+      }
     }
-  ];
+    commands
+  };
+}
+
+/// Registered commands sorted by name, without the aliases.
+pub fn commands_sorted() -> Vec<&'static Command> {
+  let mut commands: Vec<&'static Command> = inventory::iter::<Command>.into_iter().collect();
+  commands.sort_by_key(|command| command.name);
+  commands
+}
+
+#[command(name = "quit", usage = "@BOT quit", permission = Admin)]
+async fn quit(_: CallBackParams) -> CallbackReturn {
+  process::exit(0x0100)
+}
+
+#[command(name = "users", usage = "@BOT users", permission = Admin)]
+async fn list_users(_: CallBackParams) -> CallbackReturn {
+  Ok(Some(format!("{:?}", INSTANCE.write().unwrap().users)))
 }
 
-#[command]
+#[command(
+  name = "block",
+  usage = "@BOT block <user>",
+  min_args = 1,
+  permission = Admin
+)]
 async fn block_user(params: CallBackParams) -> CallbackReturn {
+  let user_id: UserId = params.arguments().next()?;
   let mut db_instance = INSTANCE.write().unwrap();
 
-  let user_id = match parse::discord_str_to_id(&params.args[1], Some(parse::DiscordIds::User)) {
-    Ok((userid, _)) => userid,
-    Err(error) => return Ok(Some(error)),
-  };
-
   db_instance.storage_add(NewStorage {
     date: None,
-    dataid: Some(user_id as i64),
+    dataid: Some(user_id.0 as i64),
     datatype: StorageDataType::Blocked as i64,
     data: "",
   });
@@ -351,88 +137,115 @@ pub struct Storage {
   pub date: Option<std::time::SystemTime>,
 }
 
-#[command]
+#[command(
+  name = "help",
+  usage = "@BOT help",
+  permission = Guest
+)]
 async fn print_help(_: CallBackParams) -> CallbackReturn {
   let mut result =
     String::from("Available commands: \nNAME => USAGE (<Args> [Optionals])| PERMISSION\n");
-  let mut commands_name: Vec<&&str> = COMMANDS_LIST.iter().map(|c| c.0).collect();
-  commands_name.sort();
-
-  for name in commands_name {
-    let command = &COMMANDS_LIST[name];
+  for command in commands_sorted() {
+    write!(result, "{}", command.name).expect("unable to append string");
+    if !command.aliases.is_empty() {
+      write!(result, " ({})", command.aliases.join(", ")).expect("unable to append string");
+    }
     writeln!(
       result,
-      "{} => Usage: {} | {{{}}}",
-      name, command.usage, command.permission
+      " => Usage: {} | {{{}}}",
+      command.usage, command.permission
     )
     .expect("unable to append string");
   }
   Ok(Some(result))
 }
 
-#[command]
+#[command(
+  name = "promote",
+  usage = "@BOT promote <@user> <role>",
+  min_args = 2,
+  permission = Admin
+)]
 async fn promote_user(params: CallBackParams) -> CallbackReturn {
-  let mut db_instance = INSTANCE.write().unwrap();
+  let mut arguments = params.arguments();
+  let user_id: UserId = arguments.next()?;
+  let role: Role = arguments.next()?;
 
-  let role = match Role::from_str(&params.args[2]) {
-    Err(_) => return Ok(Some(String::from("Role not found"))),
-    Ok(role) => role,
-  };
-
-  match parse::discord_str_to_id(&params.args[1], Some(parse::DiscordIds::User)) {
-    Ok((userid, _)) => Ok(Some(db_instance.user_role_update(userid, role))),
-    Err(error) => Ok(Some(error)),
-  }
+  let mut db_instance = INSTANCE.write().unwrap();
+  Ok(Some(db_instance.user_role_update(user_id.0, role)))
 }
 
-#[command]
+#[command(
+  name = "set-activity",
+  usage = "@BOT set-activity <ACTIVITY_NAME>",
+  min_args = 1,
+  permission = User
+)]
 async fn set_activity(params: CallBackParams) -> CallbackReturn {
+  let activity: String = params.arguments().next()?;
   params
     .context
-    .set_activity(Activity::playing(&params.args[1]))
+    .set_activity(Activity::playing(&activity))
     .await;
   let myname = &params.context.cache.current_user().name;
-  Ok(Some(format!("{} is now {} !", myname, params.args[1])))
+  Ok(Some(format!("{} is now {} !", myname, activity)))
 }
 
-#[command]
+#[command(
+  name = "send_message",
+  usage = "@BOT send_message <#channelid> <@who>",
+  min_args = 2,
+  permission = Admin
+)]
 async fn manual_send_message(params: CallBackParams) -> CallbackReturn {
-  match parse::discord_str_to_id(&params.args[1], Some(parse::DiscordIds::Channel)) {
-    Ok((chan_id, _)) => {
-      ChannelId(chan_id)
-        .send_message(&params.context.http, |m| m.content(&params.args[2]))
-        .await
-        .unwrap();
-      Ok(Some(String::from(":ok:")))
-    }
-    Err(error) => Ok(Some(error)),
-  }
+  let mut arguments = params.arguments();
+  let channel_id: ChannelId = arguments.next()?;
+  let content: String = arguments.next()?;
+
+  channel_id
+    .send_message(&params.context.http, |m| m.content(content))
+    .await?;
+  Ok(Some(String::from(":ok:")))
 }
 
-#[command]
+#[command(
+  name = "edit",
+  usage = "@BOT edit [<#channel>] <message_id> \"<new content>\"",
+  min_args = 2,
+  max_args = 3,
+  permission = User
+)]
 async fn modify_message(params: CallBackParams) -> CallbackReturn {
-  let ((channel_id, _), (message_id, _)) = if params.args.len() == 4 {
-    (
-      parse::discord_str_to_id(&params.args[1], Some(parse::DiscordIds::Channel))?,
-      parse::discord_str_to_id(&params.args[2], Some(parse::DiscordIds::Message))?,
-    )
+  let mut arguments = params.arguments();
+  let channel_id: ChannelId = if arguments.remaining() == 3 {
+    arguments.next()?
   } else {
-    (
-      (params.message.channel_id.0, parse::DiscordIds::Channel),
-      parse::discord_str_to_id(&params.args[1], Some(parse::DiscordIds::Message))?,
-    )
+    params.message.channel_id
   };
-  let mut message = ChannelId(channel_id)
-    .message(&params.context.http, message_id)
-    .await?;
+  let message_id: MessageId = arguments.next()?;
+  let content: String = arguments.next()?;
+
+  let mut message = channel_id.message(&params.context.http, message_id).await?;
   if message.is_own(&params.context.cache) {
     message
-      .edit(&params.context.http, |message| {
-        message.content(params.args.last().unwrap())
-      })
+      .edit(&params.context.http, |message| message.content(content))
       .await?;
     Ok(Some(String::from(":ok:")))
   } else {
     Ok(Some(String::from("I can only modify my own messages")))
   }
 }
+
+#[test]
+fn test_commands_registered() {
+  assert!(COMMANDS_LIST.contains_key("remindme"));
+  assert_eq!(COMMANDS_LIST["momchange"].name, "mom-change");
+  assert!(COMMANDS_LIST["quit"].permission == Role::Admin);
+  assert!(COMMANDS_LIST["remindme"].direct_message);
+  assert!(!COMMANDS_LIST["archivage"].direct_message);
+  let aliases: usize = commands_sorted()
+    .iter()
+    .map(|command| command.aliases.len())
+    .sum();
+  assert_eq!(commands_sorted().len() + aliases, COMMANDS_LIST.len());
+}
@@ -0,0 +1,268 @@
+//! Settings of each guild, stored in the database and edited with `@BOT config`.
+//!
+//! Features read the ids they need from here so a single bot can serve several guilds.
+use super::arguments::{ArgumentError, Arguments, FromArgument};
+use super::commands::{CallBackParams, CallbackReturn};
+use crate::database::INSTANCE;
+use procedural_macros::command;
+use serenity::{
+  model::id::{ChannelId, GuildId, RoleId},
+  prelude::Mentionable,
+};
+use std::{error::Error, fmt, fmt::Write, str::FromStr};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+#[derive(Copy, Clone, Debug, Display, EnumString, EnumIter, PartialEq, Eq)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ConfigKey {
+  ProjectCategory,
+  ProjectAnnouncementChannel,
+  ArchiveCategory,
+  UserRole,
+  DevopsChannel,
+  AnnoyCatsChannel,
+  AnnoyKeysChannel,
+  /// Days without message before a channel is archived
+  ArchiveInactivityDays,
+  /// Channels never archived
+  ArchiveExcludedChannels,
+  /// Summaries of the weekly archivage are posted here, it's disabled when unset
+  ArchiveReportChannel,
+  /// Members of this role approve the validations, only the author of the command without it
+  ValidationRole,
+  /// Approvals needed by a validation
+  ValidationQuorum,
+  /// Hours before a validation expires
+  ValidationExpiryHours,
+}
+
+enum ConfigKind {
+  Channel,
+  Role,
+  /// A number displayed with its unit, it can't be set below its minimum
+  Number {
+    unit: &'static str,
+    minimum: u32,
+  },
+  Channels,
+}
+
+impl ConfigKey {
+  fn kind(self) -> ConfigKind {
+    match self {
+      ConfigKey::UserRole | ConfigKey::ValidationRole => ConfigKind::Role,
+      ConfigKey::ArchiveInactivityDays => ConfigKind::Number {
+        unit: "days",
+        minimum: 1,
+      },
+      ConfigKey::ValidationQuorum => ConfigKind::Number {
+        unit: "approvals",
+        minimum: 1,
+      },
+      ConfigKey::ValidationExpiryHours => ConfigKind::Number {
+        unit: "hours",
+        minimum: 1,
+      },
+      ConfigKey::ArchiveExcludedChannels => ConfigKind::Channels,
+      _ => ConfigKind::Channel,
+    }
+  }
+
+  /// `value` is the stored value, ids are separated by commas in a list
+  fn display_value(self, value: &str) -> String {
+    let ids = value.split(',').filter_map(|id| id.parse::<u64>().ok());
+    let mentions: Vec<String> = match self.kind() {
+      ConfigKind::Role => ids.map(|id| RoleId(id).mention().to_string()).collect(),
+      ConfigKind::Number { unit, .. } => return format!("{} {}", value, unit),
+      ConfigKind::Channel | ConfigKind::Channels => {
+        ids.map(|id| ChannelId(id).mention().to_string()).collect()
+      }
+    };
+    mentions.join(", ")
+  }
+}
+
+impl FromArgument for ConfigKey {
+  const EXPECTED: &'static str = "expected a setting, see @BOT config show";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    ConfigKey::from_str(argument).map_err(|_| String::from(Self::EXPECTED))
+  }
+}
+
+/// Returned by a command when the guild is missing a setting it needs.
+#[derive(Debug)]
+pub struct MissingConfig(pub ConfigKey);
+
+impl fmt::Display for MissingConfig {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "`{}` isn't configured for this guild, use: @BOT config set {} <value>",
+      self.0, self.0
+    )
+  }
+}
+
+impl Error for MissingConfig {}
+
+pub fn get(guild_id: GuildId, key: ConfigKey) -> Option<u64> {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .guild_config_search(guild_id.0, &key.to_string())
+    .and_then(|config| config.value.parse().ok())
+}
+
+/// Ids of a setting holding several of them, empty when it isn't set
+pub fn get_list(guild_id: GuildId, key: ConfigKey) -> Vec<u64> {
+  let db_instance = INSTANCE.read().unwrap();
+  match db_instance.guild_config_search(guild_id.0, &key.to_string()) {
+    Some(config) => config
+      .value
+      .split(',')
+      .filter_map(|id| id.parse().ok())
+      .collect(),
+    None => Vec::new(),
+  }
+}
+
+pub fn require(guild_id: GuildId, key: ConfigKey) -> Result<u64, MissingConfig> {
+  get(guild_id, key).ok_or(MissingConfig(key))
+}
+
+/// Every guild having `key` set, with its value
+pub fn configured(key: ConfigKey) -> Vec<(GuildId, u64)> {
+  let name = key.to_string();
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .guild_configs
+    .iter()
+    .filter(|config| config.name == name)
+    .filter_map(|config| Some((GuildId(config.guild_id as u64), config.value.parse().ok()?)))
+    .collect()
+}
+
+fn show(guild_id: GuildId) -> String {
+  let db_instance = INSTANCE.read().unwrap();
+  let mut display = String::new();
+  for key in ConfigKey::iter() {
+    let value = match db_instance.guild_config_search(guild_id.0, &key.to_string()) {
+      Some(config) => key.display_value(&config.value),
+      None => String::from("not set"),
+    };
+    writeln!(display, "**{}**: {}", key, value).expect("unable to append in string");
+  }
+  display
+}
+
+/// The value of `key` as it's stored
+fn parse_value(key: ConfigKey, arguments: &mut Arguments) -> Result<String, ArgumentError> {
+  Ok(match key.kind() {
+    ConfigKind::Channel => arguments.next::<ChannelId>()?.0.to_string(),
+    ConfigKind::Role => arguments.next::<RoleId>()?.0.to_string(),
+    ConfigKind::Number { unit, minimum } => {
+      let number = arguments.next::<u32>()?;
+      if number < minimum {
+        return Err(ArgumentError {
+          position: 3,
+          value: Some(number.to_string()),
+          reason: format!("expected at least {} {}", minimum, unit),
+        });
+      }
+      number.to_string()
+    }
+    ConfigKind::Channels => arguments
+      .next::<Vec<ChannelId>>()?
+      .iter()
+      .map(|channel| channel.0.to_string())
+      .collect::<Vec<String>>()
+      .join(","),
+  })
+}
+
+#[command(
+  name = "config",
+  usage = "@BOT config <show | set <setting> <#channel | @role | number | #channel1,#channel2> | unset <setting>>",
+  min_args = 1,
+  max_args = 3,
+  permission = Admin
+)]
+pub async fn config(params: CallBackParams) -> CallbackReturn {
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a guild channel",
+      )))
+    }
+  };
+  let mut arguments = params.arguments();
+  match arguments.next_raw()? {
+    "show" => Ok(Some(show(guild_id))),
+    "set" => {
+      let key: ConfigKey = arguments.next()?;
+      let value = parse_value(key, &mut arguments)?;
+      let mut db_instance = INSTANCE.write().unwrap();
+      db_instance.guild_config_set(guild_id.0, &key.to_string(), &value);
+      Ok(Some(format!(
+        "{} is now {}",
+        key,
+        key.display_value(&value)
+      )))
+    }
+    "unset" => {
+      let key: ConfigKey = arguments.next()?;
+      let mut db_instance = INSTANCE.write().unwrap();
+      if db_instance.guild_config_unset(guild_id.0, &key.to_string()) {
+        Ok(Some(String::from(":ok:")))
+      } else {
+        Ok(Some(format!("{} wasn't set", key)))
+      }
+    }
+    action => Err(
+      ArgumentError {
+        position: 1,
+        value: Some(action.to_string()),
+        reason: String::from("expected show, set or unset"),
+      }
+      .into(),
+    ),
+  }
+}
+
+#[test]
+fn test_config_key() {
+  assert_eq!(
+    ConfigKey::from_str("archive-category").unwrap(),
+    ConfigKey::ArchiveCategory
+  );
+  assert_eq!(
+    ConfigKey::AnnoyCatsChannel.to_string(),
+    "annoy-cats-channel"
+  );
+  assert!(ConfigKey::from_argument("archive_category").is_err());
+
+  assert_eq!(
+    ConfigKey::ArchiveExcludedChannels.display_value("1,2"),
+    "<#1>, <#2>"
+  );
+  assert_eq!(
+    ConfigKey::ArchiveInactivityDays.display_value("30"),
+    "30 days"
+  );
+  assert_eq!(
+    ConfigKey::ValidationQuorum.display_value("2"),
+    "2 approvals"
+  );
+
+  for (value, valid) in [("0", false), ("2", true)] {
+    let args: Vec<String> = vec!["config".into(), "set".into(), value.into()];
+    let mut arguments = Arguments::new(&args);
+    arguments.next_raw().unwrap();
+    assert_eq!(
+      parse_value(ConfigKey::ValidationQuorum, &mut arguments).is_ok(),
+      valid
+    );
+  }
+}
@@ -1,8 +1,8 @@
 use super::process::{archive_activity, database_update, getbotid};
-use super::validation::{check_validation, WaitingValidation};
+use super::validation::{self, check_validation};
 use super::{api, slash_command};
 use crate::core::process::process_message;
-use crate::features::{invite_action, mecleanup, project_manager, Features};
+use crate::features::{events, invite_action, mecleanup, ordering, project_manager, Features};
 use log::{error, info};
 use serenity::http::CacheHttp;
 use serenity::model::id::ChannelId;
@@ -11,7 +11,7 @@ use serenity::{
   async_trait,
   model::{
     application::interaction::Interaction,
-    channel::{Message, Reaction},
+    channel::{Channel, GuildChannel, Message, Reaction},
     event::{MessageUpdateEvent, ResumedEvent},
     gateway::Ready,
     guild::Member,
@@ -117,10 +117,6 @@ impl EventHandler for Handler {
       match &*emoji {
         "✅" => {
           project_manager::check_subscribe(&ctx, &reaction, false).await;
-          check_validation(&ctx, &reaction, &emoji).await;
-        }
-        "❌" => {
-          check_validation(&ctx, &reaction, &emoji).await;
         }
         "🧹" => {
           mecleanup::check_mecleanup(&ctx, &reaction).await;
@@ -166,7 +162,48 @@ impl EventHandler for Handler {
   }
 
   async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-    slash_command::handle_event(interaction, ctx).await;
+    match interaction {
+      // Components are dispatched on the prefix of their custom id
+      Interaction::MessageComponent(component) => {
+        match component.data.custom_id.split(':').next() {
+          Some(events::SNOOZE_ID) => events::snooze(&ctx, &component).await,
+          Some(project_manager::subscription::SUBSCRIBE_ID) => {
+            project_manager::subscription::toggle_subscription(&ctx, &component).await
+          }
+          Some(project_manager::listing::PROJECTS_ID) => {
+            project_manager::listing::change_page(&ctx, &component).await
+          }
+          Some(validation::VALIDATION_ID) => check_validation(&ctx, &component).await,
+          _ => error!("Unknown component {}", component.data.custom_id),
+        }
+      }
+      interaction => slash_command::handle_event(interaction, ctx).await,
+    }
+  }
+
+  async fn channel_create(&self, ctx: Context, channel: &GuildChannel) {
+    ordering::auto::channel_changed(&ctx, channel, None);
+    project_manager::subscription::refresh_subscription_list(channel.guild_id, &ctx).await;
+  }
+
+  /// Only a change of name or category changes the projects
+  async fn channel_update(&self, ctx: Context, old: Option<Channel>, new: Channel) {
+    if let Channel::Guild(new) = new {
+      let old = match old {
+        Some(Channel::Guild(old)) => Some(old),
+        _ => None,
+      };
+      let unchanged =
+        matches!(&old, Some(old) if old.name == new.name && old.parent_id == new.parent_id);
+      if !unchanged {
+        ordering::auto::channel_changed(&ctx, &new, old.as_ref());
+        project_manager::subscription::refresh_subscription_list(new.guild_id, &ctx).await;
+      }
+    }
+  }
+
+  async fn channel_delete(&self, ctx: Context, channel: &GuildChannel) {
+    project_manager::subscription::refresh_subscription_list(channel.guild_id, &ctx).await;
   }
 
   async fn unknown(&self, _ctx: Context, name: String, raw: serde_json::value::Value) {
@@ -183,6 +220,8 @@ impl EventHandler for Handler {
   async fn cache_ready(&self, ctx: Context, _guilds: Vec<GuildId>) {
     info!("Cache ready");
     if !self.is_loop_running.load(Ordering::Relaxed) {
+      let http = ctx.http.clone();
+      tokio::spawn(async move { validation::expire(http).await });
       tokio::spawn(async move { api::run(ctx).await });
       self.is_loop_running.swap(true, Ordering::Relaxed);
     }
@@ -222,7 +261,6 @@ pub async fn bot_connect() {
   {
     let mut data = client.data.write().await;
     data.insert::<Features>(Features::new());
-    data.insert::<WaitingValidation>(WaitingValidation::default());
   }
 
   // Finally, start a single shard, and start listening to events.
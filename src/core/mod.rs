@@ -1,13 +1,16 @@
 //! The base of the program containing the abstractions for files and connection to discord.
 
 pub mod commands;
+pub mod config;
 pub mod eventhandler;
 // pub mod files;
 pub mod api;
+pub mod arguments;
 pub mod parse;
 pub mod permissions;
 pub mod process;
 pub mod slash_command;
+pub mod timezone;
 pub mod validation;
 
 /// Spawn thread to run core functions.
@@ -1,4 +1,4 @@
-use crate::constants::discordids;
+use chrono::Duration;
 use log::error;
 use regex::Regex;
 use serenity::{
@@ -18,8 +18,12 @@ pub enum DiscordIds {
   User,
 }
 
-pub fn main_guild_id() -> GuildId {
-  GuildId(discordids::GUILD_ID)
+/// The guild to use when none is given from a private channel, only if the bot is in a single guild
+pub fn default_guild_id(context: &Context) -> Option<GuildId> {
+  match context.cache.guilds().as_slice() {
+    [guild_id] => Some(*guild_id),
+    _ => None,
+  }
 }
 
 pub async fn get_guild(
@@ -37,50 +41,113 @@ pub async fn get_guild(
           Err(String::from("Invalid guild id"))
         }
       },
-      None => Ok(main_guild_id()),
+      None => default_guild_id(context)
+        .ok_or_else(|| String::from("I am in several guilds, give the guild id")),
     },
     Channel::Guild(guildchan) => Ok(guildchan.guild_id),
     _ => Err(String::from("This doesn't work in this channel")),
   }
 }
 
+lazy_static! {
+  static ref DISCORD_ID: Regex =
+    Regex::new(r#"^(?:<(@!?|@&|#)([0-9]{15,20})>|([0-9]{15,20}))$"#).unwrap();
+  static ref DURATION: Regex = Regex::new(
+    r#"^(?:([0-9]{1,4})(w|weeks?|semaines?|d|days?|j|jours?|h|hours?|heures?|m|min|minutes?))+$"#
+  )
+  .unwrap();
+  static ref DURATION_PART: Regex = Regex::new(
+    r#"([0-9]{1,4})(w|weeks?|semaines?|d|days?|j|jours?|h|hours?|heures?|m|min|minutes?)"#
+  )
+  .unwrap();
+}
+
+/// Parse a discord id, either raw or formated as a mention: `<@user>`, `<#channel>`, `<@&role>`
 pub fn discord_str_to_id(
   id: &str,
   exepected_type: Option<DiscordIds>,
 ) -> Result<(u64, DiscordIds), String> {
-  let size = id.len();
-  const SIZEBIGINT: usize = 18;
-  if size < SIZEBIGINT {
-    return Err(String::from("Unable to parse, text isn't an disocrd ID"));
-  }
+  let captures = match DISCORD_ID.captures(id.trim()) {
+    Some(captures) => captures,
+    None => return Err(String::from("Unable to parse, text isn't an disocrd ID")),
+  };
+  // The id is in the mention, or raw
+  let parsedid = captures
+    .get(2)
+    .or_else(|| captures.get(3))
+    .map_or("", |parsedid| parsedid.as_str())
+    .parse::<u64>()
+    .map_err(|_| String::from("Unable to parse Id, badly formated"))?;
 
-  if size == SIZEBIGINT {
-    let parsedid = id.parse::<u64>().expect("Unable to parse Id, not numeric");
-    Ok((parsedid, DiscordIds::Channel))
-  } else {
-    let parsedid = id[size - (SIZEBIGINT + 1)..size - 1]
-      .parse::<u64>()
-      .expect("Unable to parse Id, badly formated");
-    let identifier = &id[0..size - (SIZEBIGINT + 1)];
-    let discordtype: DiscordIds = match identifier {
-      "<@" | "<@!" => DiscordIds::User,
-      "<#" => DiscordIds::Channel,
-      "<@&" => DiscordIds::Role,
-      _ => DiscordIds::Channel,
-      // Channel can't be pinged so no identifier sadly
-      // _ => return Err(&*format!("Incored type for discordid: {}", identifier)),
-    };
-    if let Some(expected) = exepected_type {
-      if expected != discordtype {
-        let msg = format!(
-          "Mismatched type, expected: {}, got: {}",
-          expected, discordtype
-        );
-        return Err(msg);
-      }
+  let discordtype = match captures.get(1).map(|identifier| identifier.as_str()) {
+    Some("@") | Some("@!") => DiscordIds::User,
+    Some("@&") => DiscordIds::Role,
+    Some(_) => DiscordIds::Channel,
+    // A raw id can be anything, we trust the caller
+    None => return Ok((parsedid, exepected_type.unwrap_or(DiscordIds::Channel))),
+  };
+  if let Some(expected) = exepected_type {
+    if expected != discordtype {
+      let msg = format!(
+        "Mismatched type, expected: {}, got: {}",
+        expected, discordtype
+      );
+      return Err(msg);
     }
-    Ok((parsedid, discordtype))
   }
+  Ok((parsedid, discordtype))
+}
+
+/// Parse a duration like `10m`, `2hours`, `5d`, `3jours` or combined `1h30m`
+pub fn duration_from_str(input: &str) -> Option<Duration> {
+  if !DURATION.is_match(input) {
+    return None;
+  }
+  let mut duration = Duration::zero();
+  for captures in DURATION_PART.captures_iter(input) {
+    let number: i64 = captures[1].parse().ok()?;
+    duration = duration
+      + match &captures[2][..1] {
+        "w" | "s" => Duration::weeks(number),
+        "d" | "j" => Duration::days(number),
+        "h" => Duration::hours(number),
+        _ => Duration::minutes(number),
+      };
+  }
+  Some(duration)
+}
+
+#[test]
+fn test_discord_str_to_id() {
+  assert_eq!(
+    discord_str_to_id("<@!173013989180178432>", None),
+    Ok((173013989180178432, DiscordIds::User))
+  );
+  assert_eq!(
+    discord_str_to_id("<#1012345678901234567>", Some(DiscordIds::Channel)),
+    Ok((1012345678901234567, DiscordIds::Channel))
+  );
+  assert_eq!(
+    discord_str_to_id("173013989180178432", Some(DiscordIds::Role)),
+    Ok((173013989180178432, DiscordIds::Role))
+  );
+  assert!(discord_str_to_id("<@&173013989180178432>", Some(DiscordIds::User)).is_err());
+  assert!(discord_str_to_id("<@17301398918017843a>", None).is_err());
+  assert!(discord_str_to_id("jambon", None).is_err());
+  // The brackets are both present or both absent
+  assert!(discord_str_to_id("<@123456789012345678", None).is_err());
+  assert!(discord_str_to_id("123456789012345678>", None).is_err());
+  assert!(discord_str_to_id("<123456789012345678>", None).is_err());
+}
+
+#[test]
+fn test_duration_from_str() {
+  assert_eq!(duration_from_str("10m"), Some(Duration::minutes(10)));
+  assert_eq!(duration_from_str("2days"), Some(Duration::days(2)));
+  assert_eq!(duration_from_str("1h30m"), Some(Duration::minutes(90)));
+  assert_eq!(duration_from_str("2semaines"), Some(Duration::weeks(2)));
+  assert_eq!(duration_from_str("1h30"), None);
+  assert_eq!(duration_from_str("m"), None);
 }
 
 #[test]
@@ -1,4 +1,4 @@
-use crate::constants::discordids;
+use super::config::{self, ConfigKey};
 use crate::database;
 use serenity::{
   model::{
@@ -34,13 +34,13 @@ pub async fn is_user_allowed(
   // Only checking/updating for user or guests
   if dbrole <= database::Role::User {
     if let Channel::Guild(guildchan) = message.channel(&context.http).await.unwrap() {
+      let user_role = match config::get(guildchan.guild_id, ConfigKey::UserRole) {
+        Some(user_role) => RoleId(user_role),
+        None => return (dbrole >= expected, dbrole),
+      };
       let has_discord_role = message
         .author
-        .has_role(
-          &context.http,
-          guildchan.guild_id,
-          RoleId(discordids::USER_ROLE),
-        )
+        .has_role(&context.http, guildchan.guild_id, user_role)
         .await
         .unwrap();
       if let Some(newrole) = if has_discord_role && dbrole != database::Role::User {
@@ -77,3 +77,12 @@ pub fn member_channel_read(user: UserId, state: ReadState) -> PermissionOverwrit
     kind: PermissionOverwriteType::Member(user),
   }
 }
+
+/// Members having the role can see the channel
+pub fn role_channel_read(role: RoleId) -> PermissionOverwrite {
+  PermissionOverwrite {
+    deny: Permissions::empty(),
+    allow: Permissions::VIEW_CHANNEL,
+    kind: PermissionOverwriteType::Role(role),
+  }
+}
@@ -1,10 +1,12 @@
 //! Handle the connection with discord and it's events.
+use super::arguments::ArgumentError;
 use super::commands::{
-  CallBackParams, COMMANDS_LIST, CONTAIN_MSG_LIST, CONTAIN_REACTION_LIST, TAG_MSG_LIST,
+  CallBackParams, Command, COMMANDS_LIST, CONTAIN_MSG_LIST, CONTAIN_REACTION_LIST, TAG_MSG_LIST,
 };
+use super::config::{self, ConfigKey, MissingConfig};
 use super::permissions;
 use crate::database;
-use crate::features::funny::ATTACKED;
+use crate::features::{archivage::history, funny::ATTACKED, project_manager};
 use crate::{core::parse::split_message_args, features::gitlab_preview::gitlab_url_preview};
 use log::{debug, error};
 use serenity::model::event::MessageUpdateEvent;
@@ -31,26 +33,29 @@ pub async fn process_message(ctx: Context, message: Message) {
   //Check if i am tagged in the message else do the reactions
   // check for @me first so it's considered a command
   let botid = getbotid(&ctx).await.0;
-  if message.content.starts_with(&*format!("<@!{}>", botid))
-    || message.content.starts_with(&*format!("<@{}>", botid))
-  {
+  let tagged = message.content.starts_with(&*format!("<@!{}>", botid))
+    || message.content.starts_with(&*format!("<@{}>", botid));
+  // In a direct message with the bot the tag is optional
+  if tagged || message.is_private() {
     if attacked(&ctx, &message).await {
       return;
     }
     let line = message.content.clone();
     let mut message_split = split_message_args(&line);
 
-    // Check if there is only the tag : "@bot"
-    if message_split.len() == 1 {
-      message
-        .channel_id
-        .say(&ctx.http, "What do you need ?")
-        .await
-        .unwrap();
-      return;
+    if tagged {
+      // Check if there is only the tag : "@bot"
+      if message_split.len() == 1 {
+        message
+          .channel_id
+          .say(&ctx.http, "What do you need ?")
+          .await
+          .unwrap();
+        return;
+      }
+      // Removing tag
+      message_split.remove(0);
     }
-    // Removing tag
-    message_split.remove(0);
 
     // will go through commands.rs definitions to try and execute the request
     if !process_tag_msg(&message_split, &message, &ctx).await
@@ -98,62 +103,90 @@ async fn allowed_channel(
 }
 
 pub async fn process_command(message_split: &[String], message: &Message, ctx: &Context) -> bool {
-  for (key, command) in COMMANDS_LIST.iter() {
-    if *key == message_split[0] {
-      if !allowed_channel(command.channel, message.channel_id, ctx).await {
-        return true;
+  match COMMANDS_LIST.get(&*message_split[0]) {
+    Some(command) => {
+      execute_command(&message_split[0], command, message_split, message, ctx).await;
+      true
+    }
+    None => false,
+  }
+}
+
+/// Check the channel, the permission and the arguments before running the command,
+/// the result is then sent as a reply to `message`.
+pub async fn execute_command(
+  name: &str,
+  command: &Command,
+  message_split: &[String],
+  message: &Message,
+  ctx: &Context,
+) {
+  if !allowed_channel(command.channel, message.channel_id, ctx).await {
+    return;
+  };
+  if message.is_private() && !command.direct_message {
+    message
+      .reply(&ctx.http, "This command is restricted to a guild channel")
+      .await
+      .unwrap();
+    return;
+  }
+  let (allowed, role) = permissions::is_user_allowed(ctx, command.permission, message).await;
+  if !allowed {
+    message
+      .channel_id
+      .send_message(&ctx.http, |m| {
+        m.content(format!("You({}) are not allowed to run this command", role))
+      })
+      .await
+      .unwrap();
+    return;
+  }
+  // We remove default arguments: author and command name from the total
+  let arguments_length = message_split.len() - 1;
+  let result =
+    if arguments_length >= command.argument_min && arguments_length <= command.argument_max {
+      let params = CallBackParams {
+        args: message_split,
+        message,
+        context: ctx,
       };
-      let (allowed, role) = permissions::is_user_allowed(ctx, command.permission, message).await;
-      if !allowed {
-        message
-          .channel_id
-          .send_message(&ctx.http, |m| {
-            m.content(format!("You({}) are not allowed to run this command", role))
-          })
-          .await
-          .unwrap();
-        return true;
-      }
-      // We remove default arguments: author and command name from the total
-      let arguments_length = message_split.len() - 1;
-      let result =
-        if arguments_length >= command.argument_min && arguments_length <= command.argument_max {
-          let params = CallBackParams {
-            args: message_split,
-            message,
-            context: ctx,
-          };
-          (command.exec)(params).await
-        } else {
-          let why = if arguments_length >= command.argument_min {
-            "Too many arguments"
-          } else {
-            "No enough arguments"
-          };
-          Ok(Some(format!("{}\nUsage: {}", why, command.usage)))
-        };
+      (command.exec)(params).await
+    } else {
+      let why = if arguments_length >= command.argument_min {
+        "Too many arguments"
+      } else {
+        "No enough arguments"
+      };
+      Ok(Some(format!("{}\nUsage: {}", why, command.usage)))
+    };
 
-      match result {
-        Ok(Some(reply)) => {
-          if reply == ":ok:" {
-            message.react(&ctx.http, '✅').await.unwrap();
-          } else {
-            message.reply(&ctx.http, reply).await.unwrap();
-          }
-        }
-        Ok(None) => {}
-        Err(err) => {
-          message
-            .reply(&ctx.http, "Bipboop this is broken <@173013989180178432>")
-            .await
-            .unwrap();
-          error!("Command Error: {} => {}", key, err);
-        }
+  match result {
+    Ok(Some(reply)) => {
+      if reply == ":ok:" {
+        message.react(&ctx.http, '✅').await.unwrap();
+      } else {
+        message.reply(&ctx.http, reply).await.unwrap();
       }
-      return true;
+    }
+    Ok(None) => {}
+    Err(err) if err.is::<MissingConfig>() => {
+      message.reply(&ctx.http, err.to_string()).await.unwrap();
+    }
+    Err(err) if err.is::<ArgumentError>() => {
+      message
+        .reply(&ctx.http, format!("{}\nUsage: {}", err, command.usage))
+        .await
+        .unwrap();
+    }
+    Err(err) => {
+      message
+        .reply(&ctx.http, "Bipboop this is broken <@173013989180178432>")
+        .await
+        .unwrap();
+      error!("Command Error: {} => {}", name, err);
     }
   }
-  false
 }
 
 pub async fn process_tag_msg(message_split: &[String], message: &Message, ctx: &Context) -> bool {
@@ -184,26 +217,21 @@ const CATS: [char; 12] = [
   '😺', '😸', '😹', '😻', '😼', '😽', '🙀', '😿', '😾', '🐈', '🐁', '🐭',
 ];
 const KEYS: [char; 8] = ['🔑', '🗝', '🔏', '🔐', '🔒', '🔓', '🖱', '👓'];
-use crate::constants::discordids::{
-  ANNOYED_CHAN_CYBERGOD, ANNOYED_CHAN_HERDINGCHATTE, ANNOYED_CHAN_TESTBOT,
-};
 /// Anoying other channels
 pub async fn annoy_channel(ctx: &Context, message: &Message) {
-  if message.channel_id == ChannelId(ANNOYED_CHAN_HERDINGCHATTE) {
+  let guild_id = match message.guild_id {
+    Some(guild_id) => guild_id,
+    None => return,
+  };
+  let channel_id = Some(message.channel_id.0);
+  if channel_id == config::get(guild_id, ConfigKey::AnnoyCatsChannel) {
     let random_active = rand::random::<usize>() % 10;
     if random_active == 0 {
       let random_icon = rand::random::<usize>() % CATS.len();
       message.react(ctx, CATS[random_icon]).await.unwrap();
     }
   }
-  if message.channel_id == ChannelId(ANNOYED_CHAN_CYBERGOD) {
-    let random_active = rand::random::<usize>() % 10;
-    if random_active == 0 {
-      let random_icon = rand::random::<usize>() % KEYS.len();
-      message.react(ctx, KEYS[random_icon]).await.unwrap();
-    }
-  }
-  if message.channel_id == ChannelId(ANNOYED_CHAN_TESTBOT) {
+  if channel_id == config::get(guild_id, ConfigKey::AnnoyKeysChannel) {
     let random_active = rand::random::<usize>() % 10;
     if random_active == 0 {
       let random_icon = rand::random::<usize>() % KEYS.len();
@@ -280,6 +308,11 @@ impl From<&Message> for database::Message {
       content: val.content.clone(),
       channel: *val.channel_id.as_u64() as i64,
       date: Some(time),
+      attachments: val
+        .attachments
+        .iter()
+        .map(|attachment| attachment.url.clone())
+        .collect(),
     }
   }
 }
@@ -303,6 +336,12 @@ impl From<&MessageUpdateEvent> for database::Message {
       content: val.content.as_ref().unwrap_or(&String::new()).clone(),
       channel: *val.channel_id.as_u64() as i64,
       date: Some(time),
+      attachments: val
+        .attachments
+        .iter()
+        .flatten()
+        .map(|attachment| attachment.url.clone())
+        .collect(),
     }
   }
 }
@@ -331,31 +370,32 @@ pub fn database_update(message: database::Message, is_edit: bool) {
       date: message.date,
     });
   } else {
-    if !db_instance
-      .users
-      .iter()
-      .any(|e| e.discordid == message.author)
-    {
-      db_instance.user_add(message.author, &database::Role::Guest.to_string());
-    }
+    db_instance.user_add_missing(message.author);
     db_instance.message_add(message);
   }
 }
 
-// TODO: This is only working for 1 server as channel is static
-use crate::constants::discordids::{ARCHIVE_CATEGORY, PROJECT_CATEGORY};
+/// Move the channel back where it was archived from when a message is sent in it,
+/// to the projects when it wasn't archived by the bot
 pub async fn archive_activity(ctx: &Context, message: &Message) {
   match message.channel(&ctx.http).await {
     Ok(channel) => {
       let channelid = channel.id().0;
       match channel.guild() {
-        Some(mut channel) => {
-          if let Some(category) = channel.parent_id {
-            if category == ARCHIVE_CATEGORY {
-              channel
-                .edit(&ctx.http, |edit| edit.category(ChannelId(PROJECT_CATEGORY)))
-                .await
-                .unwrap_or_else(|_| panic!("Unable to edit channel:{} to unarchive", channel.id));
+        Some(channel) => {
+          let archive_category = config::get(channel.guild_id, ConfigKey::ArchiveCategory);
+          let project_category = config::get(channel.guild_id, ConfigKey::ProjectCategory);
+          if let (Some(category), Some(archive_category), Some(project_category)) =
+            (channel.parent_id, archive_category, project_category)
+          {
+            // Closed projects stay in the archives
+            if category == archive_category && !project_manager::state::is_closed(channel.id) {
+              // Restored by a message rather than by its author
+              if let Err(why) =
+                history::restore_channel(&ctx.http, &channel, Some(project_category), None).await
+              {
+                error!("Unable to unarchive channel {}: {}", channel.id, why);
+              }
             }
           }
         }
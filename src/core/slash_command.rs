@@ -1,85 +1,154 @@
-use std::time::SystemTime;
-
-use crate::constants;
-use crate::features::funny;
+//! Expose every command of [`COMMANDS_LIST`] as a discord slash command.
+//!
+//! [`COMMANDS_LIST`]: super::commands::COMMANDS_LIST
+use crate::database::INSTANCE;
+use log::error;
 use procedural_macros::command;
 use serenity::{
+  builder::CreateApplicationCommand,
   client::Context,
-  model::{
-    application::{
-      command::CommandOptionType,
-      interaction::{Interaction, InteractionResponseType},
+  model::application::{
+    command::CommandOptionType,
+    interaction::{
+      application_command::ApplicationCommandInteraction, Interaction, InteractionResponseType,
     },
-    id::GuildId,
   },
 };
 
-use super::commands::{CallBackParams, CallbackReturn};
+use super::commands::{commands_sorted, CallBackParams, CallbackReturn, Command, COMMANDS_LIST};
+use super::parse::{self, split_message_args};
+use super::process::{execute_command, is_user_blocked};
+
+/// Discord limits for the application commands
+const DESCRIPTION_MAX_LEN: usize = 100;
+const OPTIONS_MAX: usize = 25;
+
+fn truncate(text: &str, max: usize) -> String {
+  text.chars().take(max).collect()
+}
 
-#[command]
+/// Describe the slash command from the command definition, arguments are all strings
+/// so they are given to the callback the same way as when the bot is tagged.
+fn create_command<'a>(
+  command: &'a mut CreateApplicationCommand,
+  name: &str,
+  definition: &Command,
+) -> &'a mut CreateApplicationCommand {
+  command.name(name).description(truncate(
+    &format!("[{}] {}", definition.permission, definition.usage),
+    DESCRIPTION_MAX_LEN,
+  ));
+
+  // Usage is "@BOT <name> <arguments...>", we skip the first two words
+  let usage_arguments: Vec<String> = split_message_args(definition.usage)
+    .into_iter()
+    .skip(2)
+    .collect();
+  let options_count = definition.argument_max.min(OPTIONS_MAX);
+  for index in 0..options_count {
+    let description = if usage_arguments.len() == definition.argument_max {
+      usage_arguments[index].clone()
+    } else {
+      format!("Argument {}", index + 1)
+    };
+    command.create_option(|option| {
+      option
+        .name(format!("arg{}", index + 1))
+        .description(truncate(&description, DESCRIPTION_MAX_LEN))
+        .kind(CommandOptionType::String)
+        .required(index < definition.argument_min)
+    });
+  }
+  command
+}
+
+#[command(
+  name = "slash-command-set",
+  usage = "@BOT slash-command-set",
+  permission = Admin
+)]
 pub async fn set(params: CallBackParams) -> CallbackReturn {
-  GuildId(constants::discordids::GUILD_ID)
+  let guild = match parse::get_guild(params.message.channel_id, params.context, None).await {
+    Ok(guild) => guild,
+    Err(error) => return Ok(Some(error)),
+  };
+  guild
     .set_application_commands(&params.context.http, |commands| {
+      for definition in commands_sorted() {
+        commands.create_application_command(|command| {
+          create_command(command, definition.name, definition)
+        });
+      }
       commands
-        .create_application_command(|command| {
-          command
-            .name("mom")
-            .description("Witch user mom is currenly targeted")
-        })
-        .create_application_command(|command| {
-          command
-            .name("mom-change")
-            .description("Change the current user mom targeted")
-            .create_option(|o| {
-              o.name("user")
-                .description("Who is the new target")
-                .kind(CommandOptionType::User)
-                .required(true)
-            })
-        })
     })
-    .await
-    .unwrap();
+    .await?;
 
   Ok(Some(String::from(":ok:")))
 }
 
+/// Rebuild the arguments as if the command was written in a message: `[name, arg1, arg2...]`
+fn interaction_arguments(interaction: &ApplicationCommandInteraction) -> Vec<String> {
+  let mut options: Vec<_> = interaction.data.options.iter().collect();
+  // Options can be filled in any order, "arg" is followed by the position of the argument
+  options.sort_by_key(|option| option.name[3..].parse::<usize>().unwrap_or(usize::MAX));
+
+  let mut args = vec![interaction.data.name.clone()];
+  args.extend(
+    options
+      .iter()
+      .filter_map(|option| option.value.as_ref())
+      .map(|value| match value.as_str() {
+        Some(text) => text.to_string(),
+        None => value.to_string(),
+      }),
+  );
+  args
+}
+
 pub async fn handle_event(interaction: Interaction, ctx: Context) {
-  if let Interaction::ApplicationCommand(command) = interaction {
-    if command.data.name == "mom" {
-      let mom_result = funny::which_mom_cmdless().await;
-      command
-        .create_interaction_response(&ctx.http, |res| {
-          res
-            .kind(InteractionResponseType::ChannelMessageWithSource)
-            .interaction_response_data(|resdata| resdata.content(mom_result.unwrap()))
-        })
-        .await
-        .unwrap()
-    } else if command.data.name == "mom-change" {
-      let newuser = command
-        .data
-        .options
-        .first()
-        .unwrap()
-        .value
-        .as_ref()
-        .unwrap()
-        .as_str()
-        .unwrap();
-      let system_time_now = SystemTime::now();
+  if let Interaction::ApplicationCommand(interaction) = interaction {
+    let name = interaction.data.name.clone();
+    let args = interaction_arguments(&interaction);
+    let command = COMMANDS_LIST.get(&*name);
 
-      let mom_result =
-        funny::mom_change_cmdless(&format!("<@{}>", newuser), system_time_now.into()).await;
+    // Echo the command so it can be used as the message the command replies to
+    let echo = match command {
+      Some(_) => format!("/{} {}", name, args[1..].join(" ")),
+      None => format!("Unknown command {}", name),
+    };
+    if let Err(why) = interaction
+      .create_interaction_response(&ctx.http, |res| {
+        res
+          .kind(InteractionResponseType::ChannelMessageWithSource)
+          .interaction_response_data(|resdata| {
+            resdata
+              .content(echo)
+              .allowed_mentions(|mentions| mentions.empty_parse())
+          })
+      })
+      .await
+    {
+      return error!("Unable to respond to /{}: {}", name, why);
+    }
+    let command = match command {
+      Some(command) => command,
+      None => return,
+    };
 
-      command
-        .create_interaction_response(&ctx.http, |res| {
-          res
-            .kind(InteractionResponseType::ChannelMessageWithSource)
-            .interaction_response_data(|resdata| resdata.content(mom_result.unwrap()))
-        })
-        .await
-        .unwrap()
+    let mut message = match interaction.get_interaction_response(&ctx.http).await {
+      Ok(message) => message,
+      Err(why) => return error!("Unable to get the response of /{}: {}", name, why),
+    };
+    // The response is sent by the bot, but the command has to run as the user who issued it
+    message.author = interaction.user.clone();
+    message.guild_id = interaction.guild_id;
+    {
+      let mut db_instance = INSTANCE.write().unwrap();
+      db_instance.user_add_missing(interaction.user.id.0 as i64);
+    }
+    if is_user_blocked(&ctx, &message).await {
+      return;
     }
+    execute_command(&name, command, &args, &message, &ctx).await;
   }
 }
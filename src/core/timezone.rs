@@ -0,0 +1,85 @@
+//! Timezone of each user, set with `@BOT timezone`.
+//!
+//! Dates written by a user and the dates displayed to them are in their timezone.
+use super::arguments::FromArgument;
+use super::commands::{CallBackParams, CallbackReturn};
+use crate::database::INSTANCE;
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::{Europe::Paris, Tz};
+use procedural_macros::command;
+use serenity::model::id::UserId;
+
+/// Timezone of the users who didn't choose one
+pub const DEFAULT_TIMEZONE: Tz = Paris;
+
+impl FromArgument for Tz {
+  const EXPECTED: &'static str = "expected a timezone name: Europe/Paris, America/New_York, UTC...";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    argument.parse().map_err(|_| String::from(Self::EXPECTED))
+  }
+}
+
+pub fn user_timezone(user_id: UserId) -> Tz {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .user_search(user_id.0)
+    .and_then(|user| user.timezone.as_ref())
+    .and_then(|timezone| timezone.parse().ok())
+    .unwrap_or(DEFAULT_TIMEZONE)
+}
+
+/// The current date in the timezone of the user
+pub fn user_now(user_id: UserId) -> DateTime<Tz> {
+  user_timezone(user_id).from_utc_datetime(&Utc::now().naive_utc())
+}
+
+/// Display a date stored as utc in the timezone of the user
+pub fn format_date(date: NaiveDateTime, user_id: UserId) -> String {
+  user_timezone(user_id)
+    .from_utc_datetime(&date)
+    .format("%d/%m/%Y %H:%M")
+    .to_string()
+}
+
+#[command(
+  name = "timezone",
+  usage = "@BOT timezone [<NAME ex: Europe/Paris, America/New_York> | reset]",
+  max_args = 1,
+  permission = Guest
+)]
+pub async fn timezone(params: CallBackParams) -> CallbackReturn {
+  let user_id = params.message.author.id;
+  let mut arguments = params.arguments();
+  let timezone = match arguments.peek() {
+    None => {
+      let now = user_now(user_id);
+      return Ok(Some(format!(
+        "Your timezone is {}, it's {}",
+        now.timezone().name(),
+        now.format("%H:%M")
+      )));
+    }
+    Some("reset") => None,
+    Some(_) => Some(arguments.next::<Tz>()?),
+  };
+
+  let mut db_instance = INSTANCE.write().unwrap();
+  if !db_instance.user_timezone_update(user_id.0, timezone.map(|timezone| timezone.name())) {
+    return Ok(Some(String::from("User not found")));
+  }
+  Ok(Some(format!(
+    "Your timezone is now {}",
+    timezone.unwrap_or(DEFAULT_TIMEZONE).name()
+  )))
+}
+
+#[test]
+fn test_timezone_argument() {
+  assert_eq!(
+    Tz::from_argument("America/New_York").unwrap(),
+    chrono_tz::America::New_York
+  );
+  assert_eq!(Tz::from_argument("UTC").unwrap(), chrono_tz::UTC);
+  assert!(Tz::from_argument("Europe/Nowhere").is_err());
+}
@@ -1,75 +1,391 @@
-use futures::future::BoxFuture;
-use procedural_macros::command;
+//! Commands applied once approved with the buttons of their preview.
+//!
+//! Pending validations are stored with a description of their action so they survive a
+//! restart. Only the author of the command can approve it, or the members of
+//! `validation-role` when it's set, `validation-quorum` of them being needed. A validation
+//! not approved within `validation-expiry-hours` is struck through.
+use super::config::{self, ConfigKey};
+use crate::{
+  database::{NewValidation, Validation, INSTANCE},
+  features::{archivage, ordering},
+};
+use chrono::{Duration, Utc};
 use serenity::{
-  model::channel::{Message, Reaction},
+  builder::CreateComponents,
+  http::Http,
+  model::{
+    application::{
+      component::ButtonStyle,
+      interaction::{message_component::MessageComponentInteraction, InteractionResponseType},
+    },
+    channel::Message,
+    id::{ChannelId, GuildId, RoleId, UserId},
+  },
   prelude::*,
 };
-use std::collections::HashMap;
+use std::{error::Error, sync::Arc};
+use tokio::time;
+
+pub const VALIDATION_ID: &str = "validation";
+/// Used when the guild didn't set `validation-expiry-hours`
+const DEFAULT_EXPIRY_HOURS: i64 = 24;
+const EXPIRY_CHECK_SECS: u64 = 60;
+const OVER: &str = "This validation is over";
 
-pub type ValidationCallback = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send + Sync>;
-#[derive(Default)]
-pub struct WaitingValidation {
-  pub to_validate: HashMap<u64, ValidationCallback>,
+/// What an approved validation does, stored as JSON
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidationAction {
+  /// Move the channels to the archive category
+  Archive {
+    channels: Vec<u64>,
+    archive_category: u64,
+  },
+  /// New positions of the channels of the guild, by type of channel
+  Reorder { new_orders: Vec<Vec<u64>> },
+}
+
+impl ValidationAction {
+  async fn apply(self, validation: &Validation, ctx: &Context) {
+    let guild_id = GuildId(validation.guild_id as u64);
+    match self {
+      ValidationAction::Archive {
+        channels,
+        archive_category,
+      } => {
+        archivage::archive_validated(
+          channels,
+          ChannelId(archive_category),
+          UserId(validation.author as u64),
+          ChannelId(validation.channel_id as u64),
+          ctx,
+        )
+        .await
+      }
+      ValidationAction::Reorder { new_orders } => {
+        let new_orders = new_orders
+          .into_iter()
+          .map(|new_order| new_order.into_iter().map(ChannelId).collect())
+          .collect();
+        ordering::ordering_channels_apply(guild_id, new_orders, &ctx.http).await
+      }
+    }
+  }
 }
 
-impl TypeMapKey for WaitingValidation {
-  type Value = WaitingValidation;
+fn validation_buttons(components: &mut CreateComponents) -> &mut CreateComponents {
+  components.create_action_row(|row| {
+    row
+      .create_button(|button| {
+        button
+          .custom_id(format!("{}:approve", VALIDATION_ID))
+          .label("Approve")
+          .style(ButtonStyle::Success)
+      })
+      .create_button(|button| {
+        button
+          .custom_id(format!("{}:reject", VALIDATION_ID))
+          .label("Reject")
+          .style(ButtonStyle::Danger)
+      })
+  })
 }
 
-fn message_link(reaction: &Reaction) -> String {
+/// Last paragraph of the message, under the preview
+fn status(validation: &Validation) -> String {
+  let approvers = match validation.required_role {
+    Some(role) => format!("{}", RoleId(role as u64).mention()),
+    None => format!("{}", UserId(validation.author as u64).mention()),
+  };
   format!(
-    "https://discordapp.com/channels/{}/{}/{}",
-    reaction.guild_id.unwrap(),
-    reaction.channel_id.0,
-    reaction.message_id.0
+    "Waiting for the approval of {} ({}/{}), expires <t:{}:R>",
+    approvers,
+    validation.approvals.len(),
+    validation.quorum,
+    validation.expires_at.timestamp()
   )
 }
 
-pub async fn check_validation(ctx: &Context, reaction: &Reaction, emoji: &str) {
-  let data = &mut ctx.data.write().await;
-  let waitingvalidation = data.get_mut::<WaitingValidation>().unwrap();
-
-  let callback = waitingvalidation.to_validate.remove(&reaction.message_id.0);
-  if let Some(callback) = callback {
-    let mut message = reaction.message(&ctx.http).await.unwrap();
-    if emoji == "✅" {
-      callback().await;
-      message
-        .channel_id
-        .say(
-          &ctx.http,
-          format!(
-            "<@{}> applied {}",
-            reaction.user_id.unwrap(),
-            message_link(reaction),
-          ),
-        )
-        .await
-        .unwrap();
-    } else if emoji == "❌" {
-      let prevtext = message.content.clone();
-      message
-        .edit(&ctx.http, |message| {
-          message.content(format!("~~{}~~", prevtext))
-        })
-        .await
-        .unwrap();
-    }
+/// The content of the message without its status
+fn preview(content: &str) -> &str {
+  content
+    .rsplit_once("\n\n")
+    .map_or(content, |(preview, _)| preview)
+}
+
+/// Content of a validation that won't be applied
+fn struck(content: &str, outcome: &str) -> String {
+  format!("~~{}~~\n\n{}", preview(content), outcome)
+}
+
+fn can_approve(validation: &Validation, user_id: UserId, roles: &[RoleId]) -> bool {
+  match validation.required_role {
+    Some(role) => roles.contains(&RoleId(role as u64)),
+    None => user_id.0 as i64 == validation.author,
   }
 }
 
-#[command]
+/// Reply the preview of the command with the buttons, `action` is applied once approved
 pub async fn validate_command(
-  responsse: &str,
+  preview: &str,
   message: &Message,
   context: &Context,
-  callback: ValidationCallback,
-) -> BoxFuture<'fut, ()> {
-  let data = &mut context.data.write().await;
-  let waitingvalidation = data.get_mut::<WaitingValidation>().unwrap();
-
-  let message = message.reply(&context.http, responsse).await.unwrap();
-  message.react(&context.http, '✅').await.unwrap();
-  message.react(&context.http, '❌').await.unwrap();
-  waitingvalidation.to_validate.insert(message.id.0, callback);
+  action: ValidationAction,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+  let guild_id = message
+    .guild_id
+    .ok_or("Validations are restricted to a guild channel")?;
+  let required_role = config::get(guild_id, ConfigKey::ValidationRole);
+  // The author alone is a quorum of one
+  let quorum = match required_role {
+    Some(_) => config::get(guild_id, ConfigKey::ValidationQuorum)
+      .unwrap_or(1)
+      .max(1),
+    None => 1,
+  };
+  let expiry = config::get(guild_id, ConfigKey::ValidationExpiryHours)
+    .map_or(DEFAULT_EXPIRY_HOURS, |hours| hours as i64);
+  // Its id and the one of its message are known once sent
+  let validation = Validation {
+    id: 0,
+    guild_id: guild_id.0 as i64,
+    channel_id: message.channel_id.0 as i64,
+    message_id: 0,
+    author: message.author.id.0 as i64,
+    action: serde_json::to_string(&action)?,
+    required_role: required_role.map(|role| role as i64),
+    quorum: quorum.max(1) as i32,
+    approvals: Vec::new(),
+    expires_at: (Utc::now() + Duration::hours(expiry)).naive_utc(),
+  };
+  let reply = message
+    .channel_id
+    .send_message(&context.http, |reply| {
+      reply
+        .reference_message(message)
+        .content(format!("{}\n\n{}", preview, status(&validation)))
+        .components(validation_buttons)
+    })
+    .await?;
+  let mut db_instance = INSTANCE.write().unwrap();
+  db_instance.validation_add(NewValidation {
+    guild_id: validation.guild_id,
+    channel_id: validation.channel_id,
+    message_id: reply.id.0 as i64,
+    author: validation.author,
+    action: &validation.action,
+    required_role: validation.required_role,
+    quorum: validation.quorum,
+    expires_at: validation.expires_at,
+  });
+  Ok(())
+}
+
+async fn respond_ephemeral(
+  ctx: &Context,
+  component: &MessageComponentInteraction,
+  content: String,
+) {
+  let result = component
+    .create_interaction_response(&ctx.http, |res| {
+      res
+        .kind(InteractionResponseType::ChannelMessageWithSource)
+        .interaction_response_data(|data| data.content(content).ephemeral(true))
+    })
+    .await;
+  if let Err(why) = result {
+    error!("Unable to respond to validation: {}", why);
+  }
+}
+
+async fn update_message(
+  ctx: &Context,
+  component: &MessageComponentInteraction,
+  content: String,
+  done: bool,
+) {
+  let result = component
+    .create_interaction_response(&ctx.http, |res| {
+      res
+        .kind(InteractionResponseType::UpdateMessage)
+        .interaction_response_data(|data| {
+          data.content(content);
+          if done {
+            data.components(|components| components)
+          } else {
+            data.components(validation_buttons)
+          }
+        })
+    })
+    .await;
+  if let Err(why) = result {
+    error!("Unable to update validation: {}", why);
+  }
+}
+
+/// Called when one of the buttons of a validation is clicked
+pub async fn check_validation(ctx: &Context, component: &MessageComponentInteraction) {
+  let validation = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .validation_search(component.message.id.0)
+      .cloned()
+  };
+  let validation = match validation {
+    Some(validation) => validation,
+    None => return respond_ephemeral(ctx, component, String::from(OVER)).await,
+  };
+  let roles = component
+    .member
+    .as_ref()
+    .map_or_else(Vec::new, |member| member.roles.clone());
+  if !can_approve(&validation, component.user.id, &roles) {
+    let approvers = match validation.required_role {
+      Some(role) => format!("the members of {}", RoleId(role as u64).mention()),
+      None => format!("{}", UserId(validation.author as u64).mention()),
+    };
+    let content = format!("Only {} can answer this validation", approvers);
+    return respond_ephemeral(ctx, component, content).await;
+  }
+
+  let content = &component.message.content;
+  match component.data.custom_id.split(':').nth(1) {
+    Some("approve") => {
+      // Approved and removed at once, so a validation is applied a single time
+      let validation = {
+        let mut db_instance = INSTANCE.write().unwrap();
+        db_instance.validation_approve(validation.id, component.user.id.0)
+      };
+      let validation = match validation {
+        Some(validation) => validation,
+        None => return respond_ephemeral(ctx, component, String::from(OVER)).await,
+      };
+      if !validation.is_approved() {
+        let content = format!("{}\n\n{}", preview(content), status(&validation));
+        return update_message(ctx, component, content, false).await;
+      }
+      let approvers: Vec<String> = validation
+        .approvals
+        .iter()
+        .map(|user_id| UserId(*user_id as u64).mention().to_string())
+        .collect();
+      let applied = format!(
+        "{}\n\nApplied, approved by {}",
+        preview(content),
+        approvers.join(", ")
+      );
+      update_message(ctx, component, applied, true).await;
+      match serde_json::from_str::<ValidationAction>(&validation.action) {
+        Ok(action) => action.apply(&validation, ctx).await,
+        Err(why) => error!("Invalid validation action {}: {}", validation.action, why),
+      }
+    }
+    Some("reject") => {
+      let deleted = {
+        let mut db_instance = INSTANCE.write().unwrap();
+        db_instance.validation_delete(validation.id)
+      };
+      if !deleted {
+        return respond_ephemeral(ctx, component, String::from(OVER)).await;
+      }
+      let rejected = struck(
+        content,
+        &format!("Rejected by {}", component.user.id.mention()),
+      );
+      update_message(ctx, component, rejected, true).await;
+    }
+    _ => error!("Invalid validation button {}", component.data.custom_id),
+  }
+}
+
+/// Strike through the validations not approved in time
+pub async fn expire(http: Arc<Http>) {
+  loop {
+    let now = Utc::now().naive_utc();
+    let expired: Vec<Validation> = {
+      let db_instance = INSTANCE.read().unwrap();
+      db_instance
+        .validations
+        .iter()
+        .filter(|validation| validation.expires_at <= now)
+        .cloned()
+        .collect()
+    };
+    for validation in expired {
+      let deleted = {
+        let mut db_instance = INSTANCE.write().unwrap();
+        db_instance.validation_delete(validation.id)
+      };
+      // Answered meanwhile
+      if !deleted {
+        continue;
+      }
+      let channel_id = ChannelId(validation.channel_id as u64);
+      let message_id = validation.message_id as u64;
+      let result = match channel_id.message(&http, message_id).await {
+        Ok(message) => {
+          channel_id
+            .edit_message(&http, message_id, |edit| {
+              edit
+                .content(struck(&message.content, "Expired"))
+                .components(|components| components)
+            })
+            .await
+        }
+        Err(why) => Err(why),
+      };
+      if let Err(why) = result {
+        error!("Unable to expire validation {}: {}", message_id, why);
+      }
+    }
+    time::sleep(std::time::Duration::from_secs(EXPIRY_CHECK_SECS)).await;
+  }
+}
+
+#[test]
+fn test_validation() {
+  use chrono::NaiveDate;
+
+  let mut validation = Validation {
+    id: 1,
+    guild_id: 1,
+    channel_id: 2,
+    message_id: 3,
+    author: 4,
+    action: String::new(),
+    required_role: None,
+    quorum: 1,
+    approvals: Vec::new(),
+    expires_at: NaiveDate::from_ymd(2022, 10, 31).and_hms(12, 0, 0),
+  };
+  assert!(can_approve(&validation, UserId(4), &[]));
+  assert!(!can_approve(&validation, UserId(5), &[RoleId(6)]));
+  validation.required_role = Some(6);
+  validation.quorum = 2;
+  assert!(!can_approve(&validation, UserId(4), &[]));
+  assert!(can_approve(&validation, UserId(5), &[RoleId(6)]));
+  validation.approvals = vec![5];
+  assert!(!validation.is_approved());
+  validation.approvals = vec![5, 7];
+  assert!(validation.is_approved());
+
+  let content = format!(
+    "Order prevision:\n[general] 2 => 0\n\n{}",
+    status(&validation)
+  );
+  assert!(content.ends_with("(2/2), expires <t:1667217600:R>"));
+  assert_eq!(
+    struck(&content, "Expired"),
+    "~~Order prevision:\n[general] 2 => 0~~\n\nExpired"
+  );
+
+  let action = ValidationAction::Reorder {
+    new_orders: vec![vec![1, 2]],
+  };
+  let json = serde_json::to_string(&action).unwrap();
+  assert_eq!(json, r#"{"kind":"reorder","new_orders":[[1,2]]}"#);
+  assert_eq!(
+    serde_json::from_str::<ValidationAction>(&json).unwrap(),
+    action
+  );
 }
@@ -25,6 +25,12 @@ impl Instance {
       invites: Vec::new(),
       storage: Vec::new(),
       events: Vec::new(),
+      guild_configs: Vec::new(),
+      project_templates: Vec::new(),
+      project_members: Vec::new(),
+      archive_log: Vec::new(),
+      ordering_rules: Vec::new(),
+      validations: Vec::new(),
     };
     instance.user_load();
     instance.message_load();
@@ -33,6 +39,12 @@ impl Instance {
     instance.invites_load();
     instance.storage_load();
     instance.events_load();
+    instance.guild_configs_load();
+    instance.project_templates_load();
+    instance.project_members_load();
+    instance.archive_log_load();
+    instance.ordering_rules_load();
+    instance.validations_load();
     instance
   }
 
@@ -51,13 +63,24 @@ pub struct Instance {
   pub storage: Vec<Storage>,
   pub messages_edits: Vec<MessageEdit>,
   pub events: Vec<Event>,
+  pub guild_configs: Vec<GuildConfig>,
+  pub project_templates: Vec<ProjectTemplate>,
+  pub project_members: Vec<ProjectMember>,
+  pub archive_log: Vec<ArchiveEntry>,
+  pub ordering_rules: Vec<OrderingRule>,
+  pub validations: Vec<Validation>,
 }
 
 #[derive(Debug, Clone)]
 pub enum StorageDataType {
   Mom,
+  /// Message of the project subscription list
   ProjectBottomMessage,
   Blocked,
+  /// Warning posted in a channel by the scheduled archivage, with the id of the channel
+  ArchiveWarning,
+  /// Date of the last scheduled archivage
+  ArchiveLastRun,
 }
 
 impl From<StorageDataType> for i64 {
@@ -1,5 +1,6 @@
 #![allow(clippy::extra_unused_lifetimes)]
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::str::FromStr;
 use strum_macros::{Display, EnumString};
 
 #[derive(Queryable, Debug)]
@@ -7,6 +8,7 @@ pub struct User {
   pub id: i32,
   pub discordid: i64,
   pub role: String,
+  pub timezone: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -24,6 +26,8 @@ pub struct Message {
   pub content: String,
   pub channel: i64,
   pub date: Option<std::time::SystemTime>,
+  /// Urls of the attached files
+  pub attachments: Vec<String>,
 }
 
 #[derive(Queryable, Debug, Serialize, Clone)]
@@ -55,6 +59,14 @@ pub enum Role {
   Admin,
 }
 
+/// Where an event is sent, the channel is also used when the author can't receive direct messages
+#[derive(Copy, Clone, Debug, Display, EnumString, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Delivery {
+  Channel,
+  Dm,
+}
+
 #[derive(Queryable, Debug)]
 pub struct AirtableRow {
   pub id: i32,
@@ -71,7 +83,7 @@ pub struct NewAirtableRow {
   pub created_time: Option<std::time::SystemTime>,
 }
 
-#[derive(Queryable, Debug)]
+#[derive(Queryable, Debug, Clone)]
 pub struct Project {
   pub id: i32,
   pub message_id: i64,
@@ -84,6 +96,25 @@ pub struct Project {
   pub contexte: String,
   pub created_at: std::time::SystemTime,
   pub pinned_message_id: Option<i64>,
+  pub deadline_date: Option<NaiveDate>,
+  pub state: String,
+  pub role_id: Option<i64>,
+  pub voice_channel_id: Option<i64>,
+  pub thread_ids: Vec<i64>,
+}
+
+impl Project {
+  pub fn state(&self) -> ProjectState {
+    ProjectState::from_str(&self.state).unwrap_or(ProjectState::Active)
+  }
+}
+
+#[derive(Copy, Clone, Debug, Display, EnumString, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum ProjectState {
+  Active,
+  Paused,
+  Closed,
 }
 
 #[derive(Insertable, Debug)]
@@ -98,6 +129,59 @@ pub struct NewProject<'a> {
   pub description: Option<&'a str>,
   pub contexte: Option<&'a str>,
   pub pinned_message_id: Option<i64>,
+  pub deadline_date: Option<NaiveDate>,
+  pub role_id: Option<i64>,
+  pub voice_channel_id: Option<i64>,
+  pub thread_ids: Vec<i64>,
+}
+
+/// Only the fields set are updated
+#[derive(AsChangeset, Debug, Default)]
+#[table_name = "projects"]
+pub struct ProjectUpdate<'a> {
+  pub codex: Option<&'a str>,
+  pub client: Option<&'a str>,
+  pub lead: Option<&'a str>,
+  pub deadline: Option<&'a str>,
+  pub description: Option<&'a str>,
+  pub contexte: Option<&'a str>,
+  pub deadline_date: Option<Option<NaiveDate>>,
+  pub state: Option<&'a str>,
+}
+
+/// User having access to a project
+#[derive(Queryable, Debug, Clone)]
+pub struct ProjectMember {
+  pub id: i32,
+  pub project_id: i32,
+  pub user_id: i64,
+  pub joined_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "project_members"]
+pub struct NewProjectMember {
+  pub project_id: i32,
+  pub user_id: i64,
+}
+
+/// Resources created along the text channel of a project
+#[derive(Queryable, Debug, Clone)]
+pub struct ProjectTemplate {
+  pub id: i32,
+  pub guild_id: i64,
+  pub name: String,
+  pub voice_channel: bool,
+  pub threads: Vec<String>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "project_templates"]
+pub struct NewProjectTemplate<'a> {
+  pub guild_id: i64,
+  pub name: &'a str,
+  pub voice_channel: bool,
+  pub threads: Vec<String>,
 }
 
 #[derive(Queryable, Debug, Clone)]
@@ -143,6 +227,9 @@ pub struct Event {
   pub content: String,
   pub channel: i64,
   pub trigger_date: NaiveDateTime,
+  pub recurrence: Option<String>,
+  pub delivery: String,
+  pub project_id: Option<i32>,
 }
 
 #[derive(Insertable, Debug)]
@@ -152,6 +239,126 @@ pub struct NewEvent<'a> {
   pub content: &'a str,
   pub channel: i64,
   pub trigger_date: NaiveDateTime,
+  pub recurrence: Option<&'a str>,
+  pub delivery: &'a str,
+  pub project_id: Option<i32>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+pub struct GuildConfig {
+  pub id: i32,
+  pub guild_id: i64,
+  pub name: String,
+  pub value: String,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "guild_configs"]
+pub struct NewGuildConfig<'a> {
+  pub guild_id: i64,
+  pub name: &'a str,
+  pub value: &'a str,
+}
+
+/// Move of a channel to the archive category, `archived_by` is None for the scheduled archivage
+#[derive(Queryable, Debug, Clone)]
+pub struct ArchiveEntry {
+  pub id: i32,
+  pub guild_id: i64,
+  pub channel_id: i64,
+  pub previous_category: Option<i64>,
+  pub previous_position: i32,
+  pub archived_by: Option<i64>,
+  pub archived_at: NaiveDateTime,
+  pub restored_by: Option<i64>,
+  pub restored_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "archive_log"]
+pub struct NewArchiveEntry {
+  pub guild_id: i64,
+  pub channel_id: i64,
+  pub previous_category: Option<i64>,
+  pub previous_position: i32,
+  pub archived_by: Option<i64>,
+}
+
+/// Order of the channels of a category, `category_id` is None for the order of the
+/// categories themselves and 0 for the channels without category
+#[derive(Queryable, Debug, Clone)]
+pub struct OrderingRule {
+  pub id: i32,
+  pub guild_id: i64,
+  pub category_id: Option<i64>,
+  pub sort: String,
+  /// Channels kept at the top, in this order
+  pub pinned: Vec<i64>,
+  /// Applied again when a channel of the category is created or renamed
+  pub auto: bool,
+}
+
+impl OrderingRule {
+  pub fn sort(&self) -> OrderingSort {
+    OrderingSort::from_str(&self.sort).unwrap_or(OrderingSort::Name)
+  }
+}
+
+#[derive(Copy, Clone, Debug, Display, EnumString, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum OrderingSort {
+  Name,
+  /// By name, with the numbers compared by value
+  Natural,
+  /// The most recently active first
+  Activity,
+  /// The project with the closest deadline first
+  Deadline,
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "ordering_rules"]
+pub struct NewOrderingRule<'a> {
+  pub guild_id: i64,
+  pub category_id: Option<i64>,
+  pub sort: &'a str,
+  pub pinned: Vec<i64>,
+  pub auto: bool,
+}
+
+/// Command waiting for approval, `action` is the serialized action applied once approved
+#[derive(Queryable, Debug, Clone)]
+pub struct Validation {
+  pub id: i32,
+  pub guild_id: i64,
+  pub channel_id: i64,
+  pub message_id: i64,
+  pub author: i64,
+  pub action: String,
+  /// Role of the approvers, only the author approves without it
+  pub required_role: Option<i64>,
+  pub quorum: i32,
+  pub approvals: Vec<i64>,
+  pub expires_at: NaiveDateTime,
+}
+
+impl Validation {
+  pub fn is_approved(&self) -> bool {
+    self.approvals.len() >= self.quorum.max(1) as usize
+  }
+}
+
+#[derive(Insertable, Debug)]
+#[table_name = "validations"]
+pub struct NewValidation<'a> {
+  pub guild_id: i64,
+  pub channel_id: i64,
+  pub message_id: i64,
+  pub author: i64,
+  pub action: &'a str,
+  pub required_role: Option<i64>,
+  pub quorum: i32,
+  pub expires_at: NaiveDateTime,
 }
 
 pub use super::schema::*;
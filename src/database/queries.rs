@@ -1,6 +1,7 @@
 pub use super::models::*;
 use super::{Instance, StorageDataType};
 use crate::core::parse::DiscordIds;
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use std::error::Error;
 
@@ -17,6 +18,13 @@ impl Instance {
     self.users.push(newuser);
   }
 
+  /// Register the user as a guest the first time we see them.
+  pub fn user_add_missing(&mut self, discordid: i64) {
+    if !self.users.iter().any(|user| user.discordid == discordid) {
+      self.user_add(discordid, &Role::Guest.to_string());
+    }
+  }
+
   pub fn user_search_mut(&mut self, discordid: u64) -> Option<&mut User> {
     self
       .users
@@ -43,6 +51,25 @@ impl Instance {
     format!("Updated {} to {}", user.discordid, user.role)
   }
 
+  /// Returns false if the user isn't registered
+  pub fn user_timezone_update(&mut self, discord_id: u64, p_timezone: Option<&str>) -> bool {
+    use super::schema::users::dsl::*;
+
+    let conn = self.get_connection();
+
+    let user: &mut User = match self.user_search_mut(discord_id) {
+      Some(user) => user,
+      None => return false,
+    };
+
+    diesel::update(users.find(user.id))
+      .set(timezone.eq(p_timezone))
+      .execute(&conn)
+      .expect("Diesel: Unable to save the timezone");
+    user.timezone = p_timezone.map(String::from);
+    true
+  }
+
   pub fn user_search(&self, discordid: u64) -> Option<&User> {
     self
       .users
@@ -144,11 +171,37 @@ impl Instance {
     if let Some((index, project)) = self.projects_search(p_channel_id as i64, DiscordIds::Channel) {
       diesel::delete(projects.filter(id.eq(project.id))).execute(&self.get_connection())?;
       let project = self.projects.remove(index);
+      // The events and members of the project are deleted with it by the database
+      self
+        .events
+        .retain(|event| event.project_id != Some(project.id));
+      self
+        .project_members
+        .retain(|member| member.project_id != project.id);
       return Ok((":ok:", Some(project)));
     }
     Ok(("Channel wasn't found", None))
   }
 
+  /// Returns the updated project, None if the channel isn't a project
+  pub fn project_update(
+    &mut self,
+    p_channel_id: u64,
+    changes: &ProjectUpdate,
+  ) -> Result<Option<Project>, Box<dyn Error + Send + Sync>> {
+    use super::schema::projects::dsl::*;
+
+    let index = match self.projects_search(p_channel_id as i64, DiscordIds::Channel) {
+      Some((index, _)) => index,
+      None => return Ok(None),
+    };
+    let updated = diesel::update(projects.find(self.projects[index].id))
+      .set(changes)
+      .get_result::<Project>(&self.get_connection())?;
+    self.projects[index] = updated.clone();
+    Ok(Some(updated))
+  }
+
   db_load! {invites_load, Invite, invites}
 
   pub fn invite_search(&mut self, code: &str) -> Option<&mut Invite> {
@@ -247,6 +300,18 @@ impl Instance {
   db_load! {events_load, Event, events}
   db_add! {event_add, NewEvent, Event, events}
 
+  pub fn event_update(&mut self, event_id: i32, p_trigger_date: NaiveDateTime, p_content: &str) {
+    use super::schema::events::dsl::*;
+
+    let updated = diesel::update(events.find(event_id))
+      .set((trigger_date.eq(p_trigger_date), content.eq(p_content)))
+      .get_result::<Event>(&self.get_connection())
+      .expect("Diesel: Unable to update event");
+    if let Some(event) = self.events.iter_mut().find(|e| e.id == event_id) {
+      *event = updated;
+    }
+  }
+
   pub fn event_delete(&mut self, event_id: i32) {
     use super::schema::events::dsl::*;
 
@@ -261,4 +326,280 @@ impl Instance {
       self.events.remove(pos);
     }
   }
+
+  /// Delete the events created for a project
+  pub fn event_delete_project(&mut self, p_project_id: i32) {
+    use super::schema::events::dsl::*;
+
+    diesel::delete(events.filter(project_id.eq(p_project_id)))
+      .execute(&self.get_connection())
+      .expect("Diesel: Unable to delete the events of the project");
+    self
+      .events
+      .retain(|event| event.project_id != Some(p_project_id));
+  }
+
+  db_load! {guild_configs_load, GuildConfig, guild_configs}
+  db_add! {guild_config_add, NewGuildConfig, GuildConfig, guild_configs}
+
+  pub fn guild_config_search(&self, p_guild_id: u64, p_name: &str) -> Option<&GuildConfig> {
+    self
+      .guild_configs
+      .iter()
+      .find(|config| config.guild_id == p_guild_id as i64 && config.name == p_name)
+  }
+
+  pub fn guild_config_set(&mut self, p_guild_id: u64, p_name: &str, p_value: &str) {
+    use super::schema::guild_configs::dsl::*;
+
+    match self.guild_config_search(p_guild_id, p_name).cloned() {
+      Some(config) => {
+        let updated = diesel::update(guild_configs.find(config.id))
+          .set(value.eq(p_value))
+          .get_result::<GuildConfig>(&self.get_connection())
+          .expect("Diesel: Unable to update guild config");
+        self.guild_configs.retain(|c| c.id != config.id);
+        self.guild_configs.push(updated);
+      }
+      None => self.guild_config_add(NewGuildConfig {
+        guild_id: p_guild_id as i64,
+        name: p_name,
+        value: p_value,
+      }),
+    }
+  }
+
+  /// Returns false if the setting wasn't set
+  pub fn guild_config_unset(&mut self, p_guild_id: u64, p_name: &str) -> bool {
+    use super::schema::guild_configs::dsl::*;
+
+    let config_id = match self.guild_config_search(p_guild_id, p_name) {
+      Some(config) => config.id,
+      None => return false,
+    };
+    diesel::delete(guild_configs.find(config_id))
+      .execute(&self.get_connection())
+      .expect("Diesel: Unable to delete guild config");
+    self.guild_configs.retain(|c| c.id != config_id);
+    true
+  }
+
+  db_load! {project_templates_load, ProjectTemplate, project_templates}
+  db_add! {project_template_add, NewProjectTemplate, ProjectTemplate, project_templates}
+
+  pub fn project_template_search(&self, p_guild_id: u64, p_name: &str) -> Option<&ProjectTemplate> {
+    self
+      .project_templates
+      .iter()
+      .find(|template| template.guild_id == p_guild_id as i64 && template.name == p_name)
+  }
+
+  /// Replace the template of the same name if there is one
+  pub fn project_template_set(&mut self, new: NewProjectTemplate) {
+    use super::schema::project_templates::dsl::*;
+
+    match self.project_template_search(new.guild_id as u64, new.name) {
+      Some(template) => {
+        let template_id = template.id;
+        let updated = diesel::update(project_templates.find(template_id))
+          .set((
+            voice_channel.eq(new.voice_channel),
+            threads.eq(&new.threads),
+          ))
+          .get_result::<ProjectTemplate>(&self.get_connection())
+          .expect("Diesel: Unable to update project template");
+        self.project_templates.retain(|t| t.id != template_id);
+        self.project_templates.push(updated);
+      }
+      None => self.project_template_add(new),
+    }
+  }
+
+  /// Returns false if there was no template with this name
+  pub fn project_template_delete(&mut self, p_guild_id: u64, p_name: &str) -> bool {
+    use super::schema::project_templates::dsl::*;
+
+    let template_id = match self.project_template_search(p_guild_id, p_name) {
+      Some(template) => template.id,
+      None => return false,
+    };
+    diesel::delete(project_templates.find(template_id))
+      .execute(&self.get_connection())
+      .expect("Diesel: Unable to delete project template");
+    self.project_templates.retain(|t| t.id != template_id);
+    true
+  }
+
+  db_load! {project_members_load, ProjectMember, project_members}
+  db_add! {project_member_insert, NewProjectMember, ProjectMember, project_members}
+
+  /// Members of the project, the earliest to join first
+  pub fn project_members_of(&self, p_project_id: i32) -> Vec<&ProjectMember> {
+    let mut members: Vec<&ProjectMember> = self
+      .project_members
+      .iter()
+      .filter(|member| member.project_id == p_project_id)
+      .collect();
+    members.sort_by_key(|member| member.joined_at);
+    members
+  }
+
+  /// Nothing is done if the user is already a member
+  pub fn project_member_add(&mut self, p_project_id: i32, p_user_id: u64) {
+    if self
+      .project_members
+      .iter()
+      .any(|member| member.project_id == p_project_id && member.user_id == p_user_id as i64)
+    {
+      return;
+    }
+    self.project_member_insert(NewProjectMember {
+      project_id: p_project_id,
+      user_id: p_user_id as i64,
+    });
+  }
+
+  pub fn project_member_remove(&mut self, p_project_id: i32, p_user_id: u64) {
+    use super::schema::project_members::dsl::*;
+
+    let member_id = match self
+      .project_members
+      .iter()
+      .find(|member| member.project_id == p_project_id && member.user_id == p_user_id as i64)
+    {
+      Some(member) => member.id,
+      None => return,
+    };
+    diesel::delete(project_members.find(member_id))
+      .execute(&self.get_connection())
+      .expect("Diesel: Unable to delete project member");
+    self.project_members.retain(|m| m.id != member_id);
+  }
+
+  db_load! {archive_log_load, ArchiveEntry, archive_log}
+  db_add! {archive_entry_add, NewArchiveEntry, ArchiveEntry, archive_log}
+
+  /// Last archivage of the channel that wasn't restored yet
+  pub fn archive_entry_pending(&self, p_guild_id: u64, p_channel_id: u64) -> Option<&ArchiveEntry> {
+    self
+      .archive_log
+      .iter()
+      .filter(|entry| {
+        entry.guild_id == p_guild_id as i64
+          && entry.channel_id == p_channel_id as i64
+          && entry.restored_at.is_none()
+      })
+      .max_by_key(|entry| entry.archived_at)
+  }
+
+  pub fn archive_entry_restore(&mut self, entry_id: i32, p_restored_by: Option<u64>) {
+    use super::schema::archive_log::dsl::*;
+
+    let updated = diesel::update(archive_log.find(entry_id))
+      .set((
+        restored_by.eq(p_restored_by.map(|user_id| user_id as i64)),
+        restored_at.eq(Some(chrono::Utc::now().naive_utc())),
+      ))
+      .get_result::<ArchiveEntry>(&self.get_connection())
+      .expect("Diesel: Unable to update archive entry");
+    self.archive_log.retain(|entry| entry.id != entry_id);
+    self.archive_log.push(updated);
+  }
+
+  db_load! {ordering_rules_load, OrderingRule, ordering_rules}
+  db_add! {ordering_rule_add, NewOrderingRule, OrderingRule, ordering_rules}
+
+  pub fn ordering_rule_search(
+    &self,
+    p_guild_id: u64,
+    p_category_id: Option<u64>,
+  ) -> Option<&OrderingRule> {
+    self.ordering_rules.iter().find(|rule| {
+      rule.guild_id == p_guild_id as i64 && rule.category_id == p_category_id.map(|id| id as i64)
+    })
+  }
+
+  /// Replace the rule of the category if there is one
+  pub fn ordering_rule_set(&mut self, new: NewOrderingRule) {
+    use super::schema::ordering_rules::dsl::*;
+
+    match self.ordering_rule_search(
+      new.guild_id as u64,
+      new.category_id.map(|category| category as u64),
+    ) {
+      Some(rule) => {
+        let rule_id = rule.id;
+        let updated = diesel::update(ordering_rules.find(rule_id))
+          .set((sort.eq(new.sort), pinned.eq(&new.pinned), auto.eq(new.auto)))
+          .get_result::<OrderingRule>(&self.get_connection())
+          .expect("Diesel: Unable to update ordering rule");
+        self.ordering_rules.retain(|r| r.id != rule_id);
+        self.ordering_rules.push(updated);
+      }
+      None => self.ordering_rule_add(new),
+    }
+  }
+
+  /// Returns false if the category had no rule
+  pub fn ordering_rule_delete(&mut self, p_guild_id: u64, p_category_id: Option<u64>) -> bool {
+    use super::schema::ordering_rules::dsl::*;
+
+    let rule_id = match self.ordering_rule_search(p_guild_id, p_category_id) {
+      Some(rule) => rule.id,
+      None => return false,
+    };
+    diesel::delete(ordering_rules.find(rule_id))
+      .execute(&self.get_connection())
+      .expect("Diesel: Unable to delete ordering rule");
+    self.ordering_rules.retain(|r| r.id != rule_id);
+    true
+  }
+
+  db_load! {validations_load, Validation, validations}
+  db_add! {validation_add, NewValidation, Validation, validations}
+
+  pub fn validation_search(&self, p_message_id: u64) -> Option<&Validation> {
+    self
+      .validations
+      .iter()
+      .find(|validation| validation.message_id == p_message_id as i64)
+  }
+
+  /// Returns the validation with the approval of the user, it's removed once approved by
+  /// its quorum. None when the validation is over
+  pub fn validation_approve(&mut self, validation_id: i32, p_user_id: u64) -> Option<Validation> {
+    use super::schema::validations::dsl::*;
+
+    let index = self
+      .validations
+      .iter()
+      .position(|v| v.id == validation_id)?;
+    let mut validation = self.validations[index].clone();
+    if !validation.approvals.contains(&(p_user_id as i64)) {
+      validation.approvals.push(p_user_id as i64);
+    }
+    if validation.is_approved() {
+      self.validation_delete(validation_id);
+      return Some(validation);
+    }
+    let updated = diesel::update(validations.find(validation_id))
+      .set(approvals.eq(&validation.approvals))
+      .get_result::<Validation>(&self.get_connection())
+      .optional()
+      .expect("Diesel: Unable to update validation")?;
+    self.validations[index] = updated.clone();
+    Some(updated)
+  }
+
+  /// False when the validation was already over
+  pub fn validation_delete(&mut self, validation_id: i32) -> bool {
+    use super::schema::validations::dsl::*;
+
+    let deleted = diesel::delete(validations.find(validation_id))
+      .execute(&self.get_connection())
+      .expect("Diesel: Unable to delete validation");
+    let len = self.validations.len();
+    self.validations.retain(|v| v.id != validation_id);
+    deleted > 0 || self.validations.len() != len
+  }
 }
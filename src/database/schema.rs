@@ -7,6 +7,20 @@ table! {
     }
 }
 
+table! {
+    archive_log (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        channel_id -> Int8,
+        previous_category -> Nullable<Int8>,
+        previous_position -> Int4,
+        archived_by -> Nullable<Int8>,
+        archived_at -> Timestamp,
+        restored_by -> Nullable<Int8>,
+        restored_at -> Nullable<Timestamp>,
+    }
+}
+
 table! {
     events (id) {
         id -> Int4,
@@ -14,6 +28,18 @@ table! {
         content -> Varchar,
         channel -> Int8,
         trigger_date -> Timestamp,
+        recurrence -> Nullable<Varchar>,
+        delivery -> Varchar,
+        project_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    guild_configs (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        name -> Varchar,
+        value -> Varchar,
     }
 }
 
@@ -34,6 +60,7 @@ table! {
         content -> Varchar,
         channel -> Int8,
         date -> Nullable<Timestamp>,
+        attachments -> Array<Varchar>,
     }
 }
 
@@ -48,6 +75,36 @@ table! {
     }
 }
 
+table! {
+    ordering_rules (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        category_id -> Nullable<Int8>,
+        sort -> Varchar,
+        pinned -> Array<Int8>,
+        auto -> Bool,
+    }
+}
+
+table! {
+    project_members (id) {
+        id -> Int4,
+        project_id -> Int4,
+        user_id -> Int8,
+        joined_at -> Timestamp,
+    }
+}
+
+table! {
+    project_templates (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        name -> Varchar,
+        voice_channel -> Bool,
+        threads -> Array<Varchar>,
+    }
+}
+
 table! {
     projects (id) {
         id -> Int4,
@@ -61,6 +118,11 @@ table! {
         contexte -> Varchar,
         created_at -> Timestamp,
         pinned_message_id -> Nullable<Int8>,
+        deadline_date -> Nullable<Date>,
+        state -> Varchar,
+        role_id -> Nullable<Int8>,
+        voice_channel_id -> Nullable<Int8>,
+        thread_ids -> Array<Int8>,
     }
 }
 
@@ -79,18 +141,42 @@ table! {
         id -> Int4,
         discordid -> Int8,
         role -> Varchar,
+        timezone -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    validations (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        channel_id -> Int8,
+        message_id -> Int8,
+        author -> Int8,
+        action -> Varchar,
+        required_role -> Nullable<Int8>,
+        quorum -> Int4,
+        approvals -> Array<Int8>,
+        expires_at -> Timestamp,
     }
 }
 
+joinable!(events -> projects (project_id));
+joinable!(project_members -> projects (project_id));
 joinable!(messages_edits -> messages (parrent_message_id));
 
 allow_tables_to_appear_in_same_query!(
     airtable,
+    archive_log,
     events,
+    guild_configs,
     invites,
     messages,
     messages_edits,
+    ordering_rules,
+    project_members,
+    project_templates,
     projects,
     storage,
     users,
+    validations,
 );
@@ -1,9 +1,7 @@
-use crate::{
-  constants::discordids::USER_ROLE,
-  core::{
-    commands::{CallBackParams, CallbackReturn},
-    parse,
-  },
+use crate::core::{
+  commands::{CallBackParams, CallbackReturn},
+  config::{self, ConfigKey},
+  parse,
 };
 use log::error;
 use procedural_macros::command;
@@ -13,14 +11,21 @@ use serenity::{
   prelude::Mentionable,
 };
 
-fn get_random_user(mut users: Vec<Member>) -> Member {
-  let user_role = RoleId::from(USER_ROLE);
-  users.retain(|user| user.roles.contains(&user_role));
+/// Pick a member having the user role, any member if the guild doesn't have one configured
+fn get_random_user(mut users: Vec<Member>, user_role: Option<RoleId>) -> Member {
+  if let Some(user_role) = user_role {
+    users.retain(|user| user.roles.contains(&user_role));
+  }
   let random_index = rand::thread_rng().gen_range(0..users.len());
   users[random_index].clone()
 }
 
-#[command]
+#[command(
+  name = "anyone",
+  usage = "@BOT anyone <message>",
+  max_args = 1,
+  permission = User
+)]
 pub async fn anyone(params: CallBackParams) -> CallbackReturn {
   let http = &params.context.http;
   let channel_id = params.message.channel_id;
@@ -38,10 +43,11 @@ pub async fn anyone(params: CallBackParams) -> CallbackReturn {
       )));
     }
   };
-  let random_user = get_random_user(users);
-  let content = match params.args.len() {
-    1 => format!("{} is the chosen one", random_user.mention()),
-    _ => format!("{} {}", random_user.mention(), params.args[1]),
+  let user_role = config::get(guild, ConfigKey::UserRole).map(RoleId);
+  let random_user = get_random_user(users, user_role);
+  let content = match params.arguments().optional::<String>()? {
+    None => format!("{} is the chosen one", random_user.mention()),
+    Some(text) => format!("{} {}", random_user.mention(), text),
   };
   params
     .message
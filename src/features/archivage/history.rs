@@ -0,0 +1,244 @@
+//! Log of the channels moved to the archive category.
+//!
+//! The category and position of a channel are recorded when it's archived so
+//! `unarchive`, or a message sent in the channel, puts it back where it was.
+use super::transcript;
+use crate::{
+  core::{
+    commands::{CallBackParams, CallbackReturn},
+    timezone::format_date,
+  },
+  database::{ArchiveEntry, NewArchiveEntry, INSTANCE},
+  features::project_manager,
+};
+use procedural_macros::command;
+use serenity::{
+  http::Http,
+  model::{
+    channel::GuildChannel,
+    id::{ChannelId, UserId},
+  },
+  prelude::Mentionable,
+  Error as SerenityError,
+};
+use std::{fmt::Write, path::PathBuf};
+
+/// Number of entries displayed by `archive-log`
+const LOG_DISPLAY_LEN: usize = 20;
+
+/// Move the channel to the archives, `archived_by` is None for the scheduled archivage.
+/// Returns the path of its transcript when one was taken.
+pub async fn archive_channel(
+  http: &Http,
+  channel: &GuildChannel,
+  archive_category: ChannelId,
+  archived_by: Option<UserId>,
+) -> Result<Option<PathBuf>, SerenityError> {
+  // The channel keeps its history, it's archived even without transcript
+  let transcript = transcript::snapshot(http, channel)
+    .await
+    .unwrap_or_else(|why| {
+      error!(
+        "Unable to write the transcript of {}: {}",
+        channel.name, why
+      );
+      None
+    });
+  channel
+    .id
+    .edit(http, |edit| edit.category(archive_category))
+    .await?;
+  let mut db_instance = INSTANCE.write().unwrap();
+  db_instance.archive_entry_add(NewArchiveEntry {
+    guild_id: channel.guild_id.0 as i64,
+    channel_id: channel.id.0 as i64,
+    previous_category: channel.parent_id.map(|category| category.0 as i64),
+    previous_position: channel.position as i32,
+    archived_by: archived_by.map(|user_id| user_id.0 as i64),
+  });
+  Ok(transcript)
+}
+
+/// Category and position the channel goes back to, channels archived before the log
+/// go to `fallback` without a position
+fn restore_target(
+  entry: Option<&ArchiveEntry>,
+  fallback: Option<u64>,
+) -> Option<(Option<ChannelId>, Option<u64>)> {
+  match entry {
+    Some(entry) => Some((
+      entry.previous_category.map(|id| ChannelId(id as u64)),
+      Some(entry.previous_position.max(0) as u64),
+    )),
+    None => fallback.map(|category| (Some(ChannelId(category)), None)),
+  }
+}
+
+/// Move the channel back where it was archived from.
+/// Returns false when the channel wasn't archived by the bot and there is no fallback.
+pub async fn restore_channel(
+  http: &Http,
+  channel: &GuildChannel,
+  fallback: Option<u64>,
+  restored_by: Option<UserId>,
+) -> Result<bool, SerenityError> {
+  let entry = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .archive_entry_pending(channel.guild_id.0, channel.id.0)
+      .cloned()
+  };
+  let (category, position) = match restore_target(entry.as_ref(), fallback) {
+    Some(target) => target,
+    None => return Ok(false),
+  };
+  channel
+    .id
+    .edit(http, |edit| {
+      edit.category(category);
+      if let Some(position) = position {
+        edit.position(position);
+      }
+      edit
+    })
+    .await?;
+  if let Some(entry) = entry {
+    let mut db_instance = INSTANCE.write().unwrap();
+    db_instance.archive_entry_restore(entry.id, restored_by.map(|user_id| user_id.0));
+  }
+  Ok(true)
+}
+
+#[command(
+  name = "unarchive",
+  usage = "@BOT unarchive <#channel>",
+  min_args = 1,
+  permission = User
+)]
+pub async fn unarchive(params: CallBackParams) -> CallbackReturn {
+  let channel_id: ChannelId = params.arguments().next()?;
+  let channel = match params.context.cache.guild_channel(channel_id) {
+    Some(channel) if Some(channel.guild_id) == params.message.guild_id => channel,
+    _ => {
+      return Ok(Some(format!(
+        "{} isn't a channel of this guild",
+        channel_id.mention()
+      )))
+    }
+  };
+  // Its state would stay closed, it's reopened instead
+  if project_manager::state::is_closed(channel_id) {
+    return Ok(Some(format!(
+      "{} is a closed project, reopen it with @BOT project-reopen",
+      channel_id.mention()
+    )));
+  }
+  let restored = restore_channel(
+    &params.context.http,
+    &channel,
+    None,
+    Some(params.message.author.id),
+  )
+  .await?;
+  if restored {
+    Ok(Some(String::from(":ok:")))
+  } else {
+    Ok(Some(format!(
+      "{} wasn't archived by the bot, its previous category is unknown",
+      channel_id.mention()
+    )))
+  }
+}
+
+fn log_line(entry: &ArchiveEntry, user_id: UserId) -> String {
+  let archived_by = match entry.archived_by {
+    Some(author) => UserId(author as u64).mention().to_string(),
+    None => String::from("the scheduled archivage"),
+  };
+  let mut line = format!(
+    "{} {} archived by {}",
+    format_date(entry.archived_at, user_id),
+    ChannelId(entry.channel_id as u64).mention(),
+    archived_by
+  );
+  if let Some(restored_at) = entry.restored_at {
+    let restored_by = match entry.restored_by {
+      Some(author) => UserId(author as u64).mention().to_string(),
+      None => String::from("a message"),
+    };
+    write!(
+      line,
+      ", restored {} by {}",
+      format_date(restored_at, user_id),
+      restored_by
+    )
+    .expect("unable to append in string");
+  }
+  line
+}
+
+#[command(
+  name = "archive-log",
+  usage = "@BOT archive-log [<#channel>]",
+  max_args = 1,
+  permission = User
+)]
+pub async fn archive_log(params: CallBackParams) -> CallbackReturn {
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a guild channel",
+      )))
+    }
+  };
+  let channel_id = params.arguments().optional::<ChannelId>()?;
+  let db_instance = INSTANCE.read().unwrap();
+  let mut entries: Vec<&ArchiveEntry> = db_instance
+    .archive_log
+    .iter()
+    .filter(|entry| entry.guild_id == guild_id.0 as i64)
+    .filter(|entry| channel_id.is_none() || channel_id == Some(ChannelId(entry.channel_id as u64)))
+    .collect();
+  if entries.is_empty() {
+    return Ok(Some(String::from("Nothing was archived")));
+  }
+  entries.sort_by_key(|entry| std::cmp::Reverse(entry.archived_at));
+  let mut display = format!(
+    "Last archivages ({} of {}):\n",
+    entries.len().min(LOG_DISPLAY_LEN),
+    entries.len()
+  );
+  for entry in entries.into_iter().take(LOG_DISPLAY_LEN) {
+    writeln!(display, "{}", log_line(entry, params.message.author.id))
+      .expect("unable to append in string");
+  }
+  Ok(Some(display))
+}
+
+#[test]
+fn test_restore_target() {
+  use chrono::NaiveDate;
+
+  let entry = ArchiveEntry {
+    id: 1,
+    guild_id: 1,
+    channel_id: 2,
+    previous_category: Some(3),
+    previous_position: 4,
+    archived_by: None,
+    archived_at: NaiveDate::from_ymd(2022, 10, 30).and_hms(12, 0, 0),
+    restored_by: None,
+    restored_at: None,
+  };
+  assert_eq!(
+    restore_target(Some(&entry), Some(5)),
+    Some((Some(ChannelId(3)), Some(4)))
+  );
+  // Channels archived before the log go to the fallback where Discord puts them
+  assert_eq!(
+    restore_target(None, Some(5)),
+    Some((Some(ChannelId(5)), None))
+  );
+  assert_eq!(restore_target(None, None), None);
+}
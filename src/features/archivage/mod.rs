@@ -0,0 +1,385 @@
+use std::{collections::HashMap, fmt::Write, fs, path::PathBuf};
+
+use crate::core::{
+  arguments::FromArgument,
+  commands::{CallBackParams, CallbackReturn},
+  config::{self, ConfigKey},
+  timezone::user_timezone,
+  validation::{self, ValidationAction},
+};
+use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
+use log::error;
+use procedural_macros::command;
+use serenity::{
+  http::Http,
+  model::{
+    channel::{Channel, ChannelType, GuildChannel},
+    id::{ChannelId, GuildId, UserId},
+  },
+  prelude::*,
+};
+
+pub mod history;
+pub mod scheduled;
+pub mod transcript;
+
+/// Used when the guild didn't set `archive-inactivity-days`
+const DEFAULT_INACTIVITY_DAYS: i64 = 45;
+const MESSAGE_MAX_LEN: usize = 2000;
+/// Discord limits of attachments per message and of their size without boost
+const ATTACHMENTS_MAX: usize = 10;
+const ATTACHMENT_MAX_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Channels checked by an archivage, the arguments of the command override the guild config
+#[derive(Debug, PartialEq)]
+pub struct ArchivageOptions {
+  /// 0 for the channels without category
+  pub category: u64,
+  pub inactivity: Duration,
+  pub excluded: Vec<u64>,
+}
+
+impl ArchivageOptions {
+  pub fn from_config(guild_id: GuildId, category: u64) -> Self {
+    let days = config::get(guild_id, ConfigKey::ArchiveInactivityDays)
+      .map_or(DEFAULT_INACTIVITY_DAYS, |days| days as i64);
+    ArchivageOptions {
+      category,
+      inactivity: Duration::days(days),
+      excluded: config::get_list(guild_id, ConfigKey::ArchiveExcludedChannels),
+    }
+  }
+
+  /// `values` are the `days=` and `exclude=` arguments, they only apply to this archivage and
+  /// the excluded channels are added to the ones of the config
+  fn apply(&mut self, values: &HashMap<&str, &str>) -> Result<(), String> {
+    if let Some(days) = values.get("days") {
+      let days = u32::from_argument(days).map_err(|why| format!("Invalid days: {}", why))?;
+      self.inactivity = Duration::days(days as i64);
+    }
+    if let Some(excluded) = values.get("exclude") {
+      let excluded = Vec::<ChannelId>::from_argument(excluded)
+        .map_err(|why| format!("Invalid exclude: {}", why))?;
+      self
+        .excluded
+        .extend(excluded.into_iter().map(|channel| channel.0));
+    }
+    Ok(())
+  }
+}
+
+#[command(
+  name = "archivage",
+  usage = "@BOT archivage [report] [<category>] [days=<days>] [exclude=<#channel1>,<#channel2>]",
+  max_args = 4,
+  permission = Admin
+)]
+pub async fn archive_channels_command(params: CallBackParams) -> CallbackReturn {
+  let mut arguments = params.arguments();
+  let report = arguments.peek() == Some("report");
+  if report {
+    arguments.next_raw()?;
+  }
+  let category = match arguments.peek() {
+    Some(argument) if !argument.contains('=') => arguments.next::<ChannelId>()?.0,
+    _ => 0,
+  };
+  let values = arguments.key_values(&["days", "exclude"])?;
+  let gid = params.message.guild_id.unwrap();
+  let mut options = ArchivageOptions::from_config(gid, category);
+  if let Err(why) = options.apply(&values) {
+    return Ok(Some(why));
+  }
+  let timezone = user_timezone(params.message.author.id);
+
+  if report {
+    let report = activity_report(gid, &options, timezone, params.context);
+    for content in split_lines(&report, MESSAGE_MAX_LEN) {
+      params
+        .message
+        .channel_id
+        .say(&params.context.http, content)
+        .await?;
+    }
+    return Ok(None);
+  }
+
+  let archive_category = ChannelId(config::require(gid, ConfigKey::ArchiveCategory)?);
+  let (archivage, action) =
+    match guild_chanels_archivage(gid, &options, archive_category, timezone, params.context) {
+      Some(res) => res,
+      None => return Ok(Some(String::from("Nothing to do"))),
+    };
+
+  validation::validate_command(&archivage, params.message, params.context, action).await?;
+  Ok(None)
+}
+
+/// Returns the transcripts taken
+pub async fn move_channels_to_archive(
+  chanids: Vec<u64>,
+  archive_category: ChannelId,
+  archived_by: UserId,
+  context: &Context,
+) -> Vec<PathBuf> {
+  let cache = &context.cache;
+  let mut transcripts = Vec::new();
+  for chanid in chanids {
+    match cache.guild_channel(ChannelId(chanid)) {
+      Some(channel) => {
+        match history::archive_channel(&context.http, &channel, archive_category, Some(archived_by))
+          .await
+        {
+          Ok(Some(transcript)) => transcripts.push(transcript),
+          Ok(None) => (),
+          // TODO: Should tell the user about it
+          Err(why) => error!("Unable to edit channel {}:\n{}", channel.name, why),
+        }
+      }
+      None => error!("Channel {} not found", chanid),
+    }
+  }
+  transcripts
+}
+
+/// Archive the channels of an approved `archivage`, the transcripts are sent in `reply_channel`
+pub async fn archive_validated(
+  chanids: Vec<u64>,
+  archive_category: ChannelId,
+  archived_by: UserId,
+  reply_channel: ChannelId,
+  context: &Context,
+) {
+  let transcripts = move_channels_to_archive(chanids, archive_category, archived_by, context).await;
+  report_transcripts(&context.http, reply_channel, &transcripts).await;
+}
+
+/// Link the transcripts by their path, then attach those Discord accepts.
+/// It's best effort, the channels are already archived so failures are only logged
+pub async fn report_transcripts(http: &Http, channel_id: ChannelId, transcripts: &[PathBuf]) {
+  if transcripts.is_empty() {
+    return;
+  }
+  let paths: Vec<String> = transcripts
+    .iter()
+    .map(|path| path.display().to_string())
+    .collect();
+  let listing = format!(
+    "Transcripts of the archived channels:\n{}",
+    paths.join("\n")
+  );
+  for content in split_lines(&listing, MESSAGE_MAX_LEN) {
+    if let Err(why) = channel_id.say(http, content).await {
+      return error!("Unable to link the transcripts: {}", why);
+    }
+  }
+  let uploads: Vec<&PathBuf> = transcripts
+    .iter()
+    .filter(|path| matches!(fs::metadata(path), Ok(file) if file.len() <= ATTACHMENT_MAX_SIZE))
+    .collect();
+  for chunk in uploads.chunks(ATTACHMENTS_MAX) {
+    let result = channel_id
+      .send_message(http, |message| message.add_files(chunk.iter().copied()))
+      .await;
+    if let Err(why) = result {
+      error!("Unable to upload the transcripts: {}", why);
+    }
+  }
+}
+
+pub fn filter_guild_channel(channels: HashMap<ChannelId, Channel>) -> Vec<GuildChannel> {
+  channels
+    .into_iter()
+    .filter_map(|(_, c)| match c {
+      Channel::Guild(x) => Some(x),
+      _ => None,
+    })
+    .collect()
+}
+
+/// Date of the last message of the channel, of its creation when it has none
+pub fn last_activity(channel: &GuildChannel) -> DateTime<Utc> {
+  let timestamp = match channel.last_message_id {
+    Some(message_id) => message_id.created_at(),
+    None => channel.id.created_at(),
+  };
+  *timestamp
+}
+
+pub fn is_inactive(last_activity: DateTime<Utc>, now: DateTime<Utc>, inactivity: Duration) -> bool {
+  now - last_activity > inactivity
+}
+
+/// Text channels of the category that aren't excluded with their last activity,
+/// the least active first
+pub fn channels_activity(
+  channels: Vec<GuildChannel>,
+  options: &ArchivageOptions,
+) -> Vec<(GuildChannel, DateTime<Utc>)> {
+  let mut activity: Vec<(GuildChannel, DateTime<Utc>)> = channels
+    .into_iter()
+    .filter(|chan| {
+      matches!(chan.kind, ChannelType::Text | ChannelType::News)
+        && chan.parent_id.map_or(0, |category| category.0) == options.category
+        && !options.excluded.contains(&chan.id.0)
+    })
+    .map(|chan| {
+      let last_activity = last_activity(&chan);
+      (chan, last_activity)
+    })
+    .collect();
+  activity.sort_by_key(|(_, last_activity)| *last_activity);
+  activity
+}
+
+fn activity_line(channel: &GuildChannel, last_activity: DateTime<Utc>, timezone: Tz) -> String {
+  format!(
+    "[{}] last message: {}",
+    channel.name(),
+    last_activity
+      .with_timezone(&timezone)
+      .format("%d/%m/%Y %H:%M")
+  )
+}
+
+/// Split at the end of lines so each part fits in a message
+fn split_lines(text: &str, max_len: usize) -> Vec<String> {
+  let mut parts: Vec<String> = Vec::new();
+  for line in text.lines() {
+    match parts.last_mut() {
+      Some(part) if part.len() + line.len() < max_len => {
+        part.push('\n');
+        part.push_str(line);
+      }
+      _ => parts.push(line.chars().take(max_len).collect()),
+    }
+  }
+  parts
+}
+
+/// Last activity of every channel checked, without moving them
+fn activity_report(
+  gid: GuildId,
+  options: &ArchivageOptions,
+  timezone: Tz,
+  context: &Context,
+) -> String {
+  let channels = match context.cache.guild(gid) {
+    Some(guild) => filter_guild_channel(guild.channels),
+    None => return String::from("Guild not found"),
+  };
+  let activity = channels_activity(channels, options);
+  let now = Utc::now();
+  let mut display = format!(
+    "Activity of {} channels, inactive after {} days:\n",
+    activity.len(),
+    options.inactivity.num_days()
+  );
+  for (channel, last_activity) in activity.iter() {
+    let marker = if is_inactive(*last_activity, now, options.inactivity) {
+      " (inactive)"
+    } else {
+      ""
+    };
+    writeln!(
+      display,
+      "{}{}",
+      activity_line(channel, *last_activity, timezone),
+      marker
+    )
+    .expect("unable to append string");
+  }
+  display
+}
+
+/// Dates of the report are displayed in `timezone`
+fn check_channels_activity(
+  channels: Vec<GuildChannel>,
+  options: &ArchivageOptions,
+  timezone: Tz,
+) -> (String, Vec<u64>) {
+  let now = Utc::now();
+  let mut display = String::new();
+  let mut unactive_channels: Vec<u64> = Vec::new();
+  for (channel, last_activity) in channels_activity(channels, options) {
+    if is_inactive(last_activity, now, options.inactivity) {
+      writeln!(
+        display,
+        "{}",
+        activity_line(&channel, last_activity, timezone)
+      )
+      .expect("unable to append string");
+      unactive_channels.push(channel.id.0);
+    }
+  }
+  (display, unactive_channels)
+}
+
+pub fn guild_chanels_archivage(
+  gid: GuildId,
+  options: &ArchivageOptions,
+  archive_category: ChannelId,
+  timezone: Tz,
+  context: &Context,
+) -> Option<(String, ValidationAction)> {
+  let cache = context.cache.clone();
+  let unactive_channels = match cache.guild(gid) {
+    Some(guild) => {
+      let channels = filter_guild_channel(guild.channels);
+      check_channels_activity(channels, options, timezone)
+    }
+    None => {
+      error!("Guild not found");
+      return None;
+    }
+  };
+  if unactive_channels.1.is_empty() {
+    return None;
+  }
+  let preview_reply = format!(
+    "Unactive channels to move to archives:\n{}",
+    unactive_channels.0
+  );
+  let action = ValidationAction::Archive {
+    channels: unactive_channels.1,
+    archive_category: archive_category.0,
+  };
+  Some((preview_reply, action))
+}
+
+#[test]
+fn test_archivage_options() {
+  let mut options = ArchivageOptions {
+    category: 0,
+    inactivity: Duration::days(45),
+    excluded: vec![1],
+  };
+  let values = HashMap::from([
+    ("days", "10"),
+    ("exclude", "<#223456789012345678>,<#323456789012345678>"),
+  ]);
+  options.apply(&values).unwrap();
+  assert_eq!(options.inactivity, Duration::days(10));
+  assert_eq!(
+    options.excluded,
+    vec![1, 223456789012345678, 323456789012345678]
+  );
+  assert!(options.apply(&HashMap::from([("days", "-1")])).is_err());
+
+  let now = Utc.ymd(2022, 10, 30).and_hms(12, 0, 0);
+  assert!(is_inactive(
+    Utc.ymd(2022, 9, 1).and_hms(12, 0, 0),
+    now,
+    Duration::days(45)
+  ));
+  // A channel active late the day before the threshold isn't inactive yet
+  assert!(!is_inactive(
+    Utc.ymd(2022, 9, 15).and_hms(23, 0, 0),
+    now,
+    Duration::days(45)
+  ));
+
+  let text = "a".repeat(5) + "\n" + &"b".repeat(5) + "\n" + &"c".repeat(3);
+  assert_eq!(split_lines(&text, 12), vec!["aaaaa\nbbbbb", "ccc"]);
+}
@@ -0,0 +1,208 @@
+//! Weekly archivage of the inactive channels of the project category.
+//!
+//! A warning is posted in the channels that will be inactive for `archive-inactivity-days`
+//! within a week. At the next run, the channels where nobody wrote since the warning are
+//! moved to the archives and a summary is posted in `archive-report-channel`.
+//! Guilds without a report channel aren't archived automatically.
+use super::{
+  channels_activity, history::archive_channel, is_inactive, report_transcripts, ArchivageOptions,
+};
+use crate::{
+  core::config::{self, ConfigKey},
+  database::{NewStorage, StorageDataType, INSTANCE},
+};
+use chrono::{DateTime, Duration, Utc};
+use serenity::{
+  http::Http,
+  model::{
+    channel::GuildChannel,
+    id::{ChannelId, GuildId, MessageId},
+  },
+  prelude::Mentionable,
+};
+use std::{sync::Arc, time::SystemTime};
+use tokio::time;
+
+/// Time between two runs, it's also the notice given by the warnings
+const ARCHIVE_INTERVAL_DAYS: i64 = 7;
+
+fn next_run(last_run: Option<DateTime<Utc>>, now: DateTime<Utc>) -> DateTime<Utc> {
+  match last_run {
+    Some(last_run) => (last_run + Duration::days(ARCHIVE_INTERVAL_DAYS)).max(now),
+    None => now,
+  }
+}
+
+fn last_run() -> Option<DateTime<Utc>> {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .find_storage_type(StorageDataType::ArchiveLastRun)
+    .and_then(|stored| stored.date)
+    .map(DateTime::from)
+}
+
+fn save_run(date: DateTime<Utc>) {
+  let mut db_instance = INSTANCE.write().unwrap();
+  let previous = db_instance
+    .filter_storage_type(StorageDataType::ArchiveLastRun)
+    .iter()
+    .map(|stored| stored.id)
+    .collect();
+  db_instance.storage_delete(previous);
+  db_instance.storage_add(NewStorage {
+    datatype: StorageDataType::ArchiveLastRun.into(),
+    dataid: None,
+    data: "",
+    date: Some(date.into()),
+  });
+}
+
+/// Channels warned by the previous run with the id of the warning, they are forgotten
+fn take_warnings() -> Vec<(ChannelId, MessageId)> {
+  let mut db_instance = INSTANCE.write().unwrap();
+  let stored = db_instance.filter_storage_type(StorageDataType::ArchiveWarning);
+  let warnings = stored
+    .iter()
+    .filter_map(|warning| {
+      Some((
+        ChannelId(warning.data.parse().ok()?),
+        MessageId(warning.dataid? as u64),
+      ))
+    })
+    .collect();
+  let stored_ids = stored.iter().map(|warning| warning.id).collect();
+  db_instance.storage_delete(stored_ids);
+  warnings
+}
+
+fn summary(archived: &[GuildChannel], warned: &[GuildChannel]) -> Option<String> {
+  let mentions = |channels: &[GuildChannel]| {
+    channels
+      .iter()
+      .map(|channel| channel.mention().to_string())
+      .collect::<Vec<String>>()
+      .join(", ")
+  };
+  let mut lines = Vec::new();
+  if !archived.is_empty() {
+    lines.push(format!("Archived: {}", mentions(archived)));
+  }
+  if !warned.is_empty() {
+    lines.push(format!(
+      "Archived in {} days unless someone writes there: {}",
+      ARCHIVE_INTERVAL_DAYS,
+      mentions(warned)
+    ));
+  }
+  if lines.is_empty() {
+    return None;
+  }
+  Some(format!("Weekly archivage\n{}", lines.join("\n")))
+}
+
+async fn archive_guild(
+  http: &Http,
+  guild_id: GuildId,
+  report_channel: ChannelId,
+  warnings: &[(ChannelId, MessageId)],
+) {
+  let (project_category, archive_category) = match (
+    config::get(guild_id, ConfigKey::ProjectCategory),
+    config::get(guild_id, ConfigKey::ArchiveCategory),
+  ) {
+    (Some(project_category), Some(archive_category)) => {
+      (project_category, ChannelId(archive_category))
+    }
+    _ => {
+      return warn!(
+        "Guild {} can't be archived, its categories aren't set",
+        guild_id
+      )
+    }
+  };
+  let channels: Vec<GuildChannel> = match guild_id.channels(http).await {
+    Ok(channels) => channels.into_values().collect(),
+    Err(why) => return error!("Unable to get the channels of {}: {}", guild_id, why),
+  };
+  let options = ArchivageOptions::from_config(guild_id, project_category);
+  let warning_date = Utc::now() + Duration::days(ARCHIVE_INTERVAL_DAYS);
+
+  let mut archived = Vec::new();
+  let mut warned = Vec::new();
+  let mut transcripts = Vec::new();
+  for (channel, last_activity) in channels_activity(channels, &options) {
+    let warning = warnings
+      .iter()
+      .find(|(channel_id, _)| *channel_id == channel.id)
+      .map(|(_, warning_id)| *warning_id);
+    // The warning is still the last message, nobody wrote since
+    if warning.is_some() && channel.last_message_id == warning {
+      match archive_channel(http, &channel, archive_category, None).await {
+        Ok(transcript) => {
+          transcripts.extend(transcript);
+          archived.push(channel);
+        }
+        Err(why) => error!("Unable to archive {}: {}", channel.name, why),
+      }
+    } else if is_inactive(last_activity, warning_date, options.inactivity) {
+      let content = format!(
+        "This channel will be archived in {} days unless someone writes here",
+        ARCHIVE_INTERVAL_DAYS
+      );
+      match channel.id.say(http, content).await {
+        Ok(message) => {
+          let mut db_instance = INSTANCE.write().unwrap();
+          db_instance.storage_add(NewStorage {
+            datatype: StorageDataType::ArchiveWarning.into(),
+            dataid: Some(message.id.0 as i64),
+            data: &channel.id.0.to_string(),
+            date: Some(SystemTime::from(*message.timestamp)),
+          });
+          warned.push(channel);
+        }
+        Err(why) => error!("Unable to warn {}: {}", channel.name, why),
+      }
+    }
+  }
+
+  if let Some(content) = summary(&archived, &warned) {
+    if let Err(why) = report_channel.say(http, content).await {
+      error!("Unable to report the archivage of {}: {}", guild_id, why);
+    }
+  }
+  // The summary doesn't depend on the transcripts being accepted by Discord
+  report_transcripts(http, report_channel, &transcripts).await;
+}
+
+pub async fn run(http: Arc<Http>) {
+  info!("running scheduled archivage");
+  loop {
+    let now = Utc::now();
+    let sleep = next_run(last_run(), now) - now;
+    time::sleep(sleep.to_std().unwrap_or_default()).await;
+
+    let warnings = take_warnings();
+    for (guild_id, report_channel) in config::configured(ConfigKey::ArchiveReportChannel) {
+      archive_guild(&http, guild_id, ChannelId(report_channel), &warnings).await;
+    }
+    save_run(Utc::now());
+  }
+}
+
+#[test]
+fn test_next_run() {
+  use chrono::TimeZone;
+
+  let now = Utc.ymd(2022, 10, 30).and_hms(12, 0, 0);
+  assert_eq!(next_run(None, now), now);
+  assert_eq!(
+    next_run(Some(Utc.ymd(2022, 10, 27).and_hms(8, 0, 0)), now),
+    Utc.ymd(2022, 11, 3).and_hms(8, 0, 0)
+  );
+  // A run missed while the bot was down happens right away
+  assert_eq!(
+    next_run(Some(Utc.ymd(2022, 10, 1).and_hms(8, 0, 0)), now),
+    now
+  );
+  assert_eq!(summary(&[], &[]), None);
+}
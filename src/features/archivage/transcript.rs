@@ -0,0 +1,343 @@
+//! Transcript of a channel written before it's archived.
+//!
+//! The history is fetched from Discord and completed with the `messages` table, which
+//! keeps the edits and the messages deleted since. It's written as gzipped JSON and HTML
+//! in `ARCHIVE_TRANSCRIPTS_DIR`, no transcript is taken when it isn't set.
+use crate::database::INSTANCE;
+use chrono::{DateTime, Utc};
+use flate2::{write::GzEncoder, Compression};
+use serenity::{
+  http::Http,
+  model::{
+    channel::{GuildChannel, Message},
+    id::MessageId,
+  },
+  Error as SerenityError,
+};
+use std::{
+  collections::HashMap,
+  env,
+  error::Error,
+  fmt::Write as FmtWrite,
+  fs::{self, File},
+  io::Write,
+  path::{Path, PathBuf},
+  time::SystemTime,
+};
+
+lazy_static! {
+  static ref TRANSCRIPTS_DIR: Option<PathBuf> =
+    env::var("ARCHIVE_TRANSCRIPTS_DIR").ok().map(PathBuf::from);
+}
+
+/// Maximum number of messages Discord returns per request
+const PAGE_LEN: u64 = 100;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TranscriptEdit {
+  pub date: Option<DateTime<Utc>>,
+  pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranscriptMessage {
+  pub id: u64,
+  pub author: u64,
+  /// Known for the messages still on Discord
+  pub author_name: Option<String>,
+  pub date: Option<DateTime<Utc>>,
+  /// Content when it was sent
+  pub content: String,
+  pub attachments: Vec<String>,
+  /// Later versions of the content, the oldest first
+  pub edits: Vec<TranscriptEdit>,
+  /// Only in the database, it was deleted from Discord
+  pub deleted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Transcript {
+  pub guild_id: u64,
+  pub channel_id: u64,
+  pub channel_name: String,
+  pub taken_at: DateTime<Utc>,
+  pub messages: Vec<TranscriptMessage>,
+}
+
+/// Every message of the channel, fetched page by page from the newest
+async fn fetch_history(http: &Http, channel: &GuildChannel) -> Result<Vec<Message>, SerenityError> {
+  let mut history: Vec<Message> = Vec::new();
+  let mut before: Option<MessageId> = None;
+  loop {
+    let page = channel
+      .id
+      .messages(http, |retriever| {
+        if let Some(before) = before {
+          retriever.before(before);
+        }
+        retriever.limit(PAGE_LEN)
+      })
+      .await?;
+    let len = page.len() as u64;
+    before = page.last().map(|message| message.id);
+    history.extend(page);
+    if len < PAGE_LEN {
+      return Ok(history);
+    }
+  }
+}
+
+/// Messages of the channel in the database with their edits
+fn stored_history(channel: &GuildChannel) -> Vec<TranscriptMessage> {
+  let db_instance = INSTANCE.read().unwrap();
+  let channel_id = channel.id.0 as i64;
+  let mut edits: HashMap<i64, Vec<(Option<SystemTime>, String)>> = HashMap::new();
+  for edit in db_instance
+    .messages_edits
+    .iter()
+    .filter(|edit| edit.channel == channel_id)
+  {
+    edits
+      .entry(edit.parrent_message_id)
+      .or_default()
+      .push((edit.date, edit.content.clone()));
+  }
+  db_instance
+    .messages
+    .iter()
+    .filter(|message| message.channel == channel_id)
+    .map(|message| {
+      let mut edits = edits.remove(&message.id).unwrap_or_default();
+      edits.sort_by_key(|(date, _)| *date);
+      TranscriptMessage {
+        id: message.id as u64,
+        author: message.author as u64,
+        author_name: None,
+        date: message.date.map(DateTime::from),
+        content: message.content.clone(),
+        attachments: message.attachments.clone(),
+        edits: edits
+          .into_iter()
+          .map(|(date, content)| TranscriptEdit {
+            date: date.map(DateTime::from),
+            content,
+          })
+          .collect(),
+        deleted: true,
+      }
+    })
+    .collect()
+}
+
+/// Discord has the current content, the database the original one, the edits and the
+/// deleted messages
+fn merge_history(
+  discord: Vec<TranscriptMessage>,
+  stored: Vec<TranscriptMessage>,
+) -> Vec<TranscriptMessage> {
+  let mut messages: Vec<TranscriptMessage> = Vec::new();
+  let mut stored = stored;
+  for mut message in discord {
+    if let Some(index) = stored.iter().position(|other| other.id == message.id) {
+      let original = stored.swap_remove(index);
+      let current = std::mem::replace(&mut message.content, original.content);
+      message.edits = original.edits;
+      // The edits made while the bot was down aren't in the database
+      let latest = message
+        .edits
+        .last()
+        .map_or(&message.content, |edit| &edit.content);
+      if *latest != current {
+        message.edits.push(TranscriptEdit {
+          date: None,
+          content: current,
+        });
+      }
+    }
+    messages.push(message);
+  }
+  messages.extend(stored);
+  messages.sort_by_key(|message| (message.date, message.id));
+  messages
+}
+
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+fn format_date(date: Option<DateTime<Utc>>) -> String {
+  date.map_or_else(
+    || String::from("N/A"),
+    |date| date.format("%d/%m/%Y %H:%M").to_string(),
+  )
+}
+
+fn to_html(transcript: &Transcript) -> String {
+  let mut html = format!(
+    "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>#{name}</title></head>\n<body>\n<h1>#{name}</h1>\n<p>{count} messages, taken {date}</p>\n",
+    name = escape_html(&transcript.channel_name),
+    count = transcript.messages.len(),
+    date = transcript.taken_at.format("%d/%m/%Y %H:%M UTC"),
+  );
+  for message in transcript.messages.iter() {
+    let author = match &message.author_name {
+      Some(name) => escape_html(name),
+      None => message.author.to_string(),
+    };
+    let date = format_date(message.date);
+    writeln!(
+      html,
+      "<div class=\"message{}\">\n<p><b>{}</b> {}{}</p>\n<p>{}</p>",
+      if message.deleted { " deleted" } else { "" },
+      author,
+      date,
+      if message.deleted { " (deleted)" } else { "" },
+      escape_html(&message.content).replace('\n', "<br>")
+    )
+    .expect("unable to append in string");
+    for attachment in message.attachments.iter() {
+      writeln!(
+        html,
+        "<p><a href=\"{0}\">{0}</a></p>",
+        escape_html(attachment)
+      )
+      .expect("unable to append in string");
+    }
+    for edit in message.edits.iter() {
+      writeln!(
+        html,
+        "<p><i>Edited {}: {}</i></p>",
+        format_date(edit.date),
+        escape_html(&edit.content).replace('\n', "<br>")
+      )
+      .expect("unable to append in string");
+    }
+    html.push_str("</div>\n");
+  }
+  html.push_str("</body>\n</html>\n");
+  html
+}
+
+fn write_gzip(path: &Path, content: &str) -> std::io::Result<()> {
+  let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+  encoder.write_all(content.as_bytes())?;
+  encoder.finish()?;
+  Ok(())
+}
+
+/// Returns the path of the HTML transcript, the JSON one is next to it
+fn write_transcript(
+  directory: &Path,
+  transcript: &Transcript,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+  fs::create_dir_all(directory)?;
+  let name = format!(
+    "{}-{}-{}",
+    transcript.channel_name,
+    transcript.channel_id,
+    transcript.taken_at.format("%Y%m%d%H%M%S")
+  );
+  let json_path = directory.join(format!("{}.json.gz", name));
+  write_gzip(&json_path, &serde_json::to_string(transcript)?)?;
+  let html_path = directory.join(format!("{}.html.gz", name));
+  write_gzip(&html_path, &to_html(transcript))?;
+  Ok(html_path)
+}
+
+/// Write the transcript of the channel when `ARCHIVE_TRANSCRIPTS_DIR` is set
+pub async fn snapshot(
+  http: &Http,
+  channel: &GuildChannel,
+) -> Result<Option<PathBuf>, Box<dyn Error + Send + Sync>> {
+  let directory = match TRANSCRIPTS_DIR.as_ref() {
+    Some(directory) => directory,
+    None => return Ok(None),
+  };
+  let discord = fetch_history(http, channel)
+    .await?
+    .into_iter()
+    .map(|message| TranscriptMessage {
+      id: message.id.0,
+      author: message.author.id.0,
+      author_name: Some(message.author.tag()),
+      date: Some(*message.timestamp),
+      content: message.content,
+      attachments: message
+        .attachments
+        .into_iter()
+        .map(|attachment| attachment.url)
+        .collect(),
+      edits: Vec::new(),
+      deleted: false,
+    })
+    .collect();
+  let transcript = Transcript {
+    guild_id: channel.guild_id.0,
+    channel_id: channel.id.0,
+    channel_name: channel.name.clone(),
+    taken_at: Utc::now(),
+    messages: merge_history(discord, stored_history(channel)),
+  };
+  // Compressed and written out of the async runtime
+  let directory = directory.clone();
+  tokio::task::spawn_blocking(move || write_transcript(&directory, &transcript))
+    .await?
+    .map(Some)
+}
+
+#[test]
+fn test_transcript() {
+  use chrono::TimeZone;
+
+  let message = |id: u64, hour: u32, content: &str, deleted: bool| TranscriptMessage {
+    id,
+    author: 7,
+    author_name: None,
+    date: Some(Utc.ymd(2022, 10, 30).and_hms(hour, 0, 0)),
+    content: String::from(content),
+    attachments: Vec::new(),
+    edits: Vec::new(),
+    deleted,
+  };
+  let mut edited = message(1, 9, "hello", true);
+  edited.edits.push(TranscriptEdit {
+    date: Some(Utc.ymd(2022, 10, 30).and_hms(9, 30, 0)),
+    content: String::from("hello v2"),
+  });
+  let messages = merge_history(
+    vec![
+      message(1, 9, "hello v3", false),
+      message(3, 11, "<b>", false),
+    ],
+    vec![edited, message(2, 10, "gone", true)],
+  );
+  let ids: Vec<u64> = messages.iter().map(|message| message.id).collect();
+  assert_eq!(ids, vec![1, 2, 3]);
+  assert!(!messages[0].deleted);
+  assert_eq!(messages[0].content, "hello");
+  let edits: Vec<&str> = messages[0]
+    .edits
+    .iter()
+    .map(|edit| edit.content.as_str())
+    .collect();
+  assert_eq!(edits, vec!["hello v2", "hello v3"]);
+  assert!(messages[1].deleted);
+  assert!(messages[2].edits.is_empty());
+
+  let html = to_html(&Transcript {
+    guild_id: 1,
+    channel_id: 2,
+    channel_name: String::from("project"),
+    taken_at: Utc.ymd(2022, 10, 31).and_hms(8, 0, 0),
+    messages,
+  });
+  assert!(html.contains("<p>hello</p>"));
+  assert!(html.contains("<p><i>Edited 30/10/2022 09:30: hello v2</i></p>"));
+  assert!(html.contains("<p><i>Edited N/A: hello v3</i></p>"));
+  assert!(html.contains("<p>&lt;b&gt;</p>"));
+  assert!(html.contains("30/10/2022 10:00 (deleted)"));
+}
@@ -1,5 +1,9 @@
-use crate::core::commands::{CallBackParams, CallbackReturn};
-use chrono::{NaiveDate, NaiveDateTime, Utc};
+use crate::core::{
+  arguments::FromArgument,
+  commands::{CallBackParams, CallbackReturn},
+  timezone::user_now,
+};
+use chrono::{NaiveDate, NaiveDateTime};
 use log::error;
 use procedural_macros::command;
 use reqwest::Client;
@@ -57,16 +61,31 @@ impl From<i32> for WarnReason {
   }
 }
 
-#[command]
+/// The first day of a month given as MM/YYYY
+struct Month(NaiveDate);
+
+impl FromArgument for Month {
+  const EXPECTED: &'static str = "expected a month: MM/YYYY";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    NaiveDate::parse_from_str(&format!("01/{}", argument), "%d/%m/%Y")
+      .map(Month)
+      .map_err(|_| String::from(Self::EXPECTED))
+  }
+}
+
+#[command(
+  name = "check-calendar",
+  aliases = ["check_calendar"],
+  usage = "@BOT check_calendar <date = MM/AAAA>",
+  max_args = 1,
+  permission = User
+)]
 pub async fn check_calendar(params: CallBackParams) -> CallbackReturn {
   let client = Client::new();
-  let date = if params.args.len() > 1 {
-    match NaiveDate::parse_from_str(&format!("01/{}", params.args[1]), "%d/%m/%Y") {
-      Ok(date) => date,
-      Err(_) => return Ok(Some(String::from("Invalid date format"))),
-    }
-  } else {
-    Utc::now().naive_local().date()
+  let date = match params.arguments().optional::<Month>()? {
+    Some(Month(date)) => date,
+    None => user_now(params.message.author.id).naive_local().date(),
   };
   let warnings = match client
     .get(format!("{}/warnings/", *CRA_SERVER))
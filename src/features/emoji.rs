@@ -1,38 +1,60 @@
 use procedural_macros::command;
-use serenity::model::prelude::GuildId;
 
-use crate::{
-  constants::discordids::GUILD_ID,
-  core::{
-    commands::{CallBackParams, CallbackReturn},
-    parse::emoji_str_convert,
-  },
+use crate::core::{
+  arguments::FromArgument,
+  commands::{CallBackParams, CallbackReturn},
+  parse::{self, emoji_str_convert},
 };
 
-#[command]
-pub async fn add(params: CallBackParams<'_>) -> CallbackReturn {
-  if let Some((is_animated, emoji_name, emoji_id)) = emoji_str_convert(&params.args[1]) {
-    let extension = if is_animated { "gif" } else { "png" };
-    let url = format!(
-      "https://cdn.discordapp.com/emojis/{}.{}?size=128",
-      emoji_id, extension
-    );
+struct CustomEmoji {
+  animated: bool,
+  name: String,
+  id: String,
+}
 
-    let client = reqwest::Client::builder().build()?;
-    let response = client.get(url).send().await.unwrap();
-    let response_body = response.bytes().await.unwrap();
-    let base64_img = format!(
-      "data:image/{};base64,{}",
-      extension,
-      base64::encode(response_body)
-    );
+impl FromArgument for CustomEmoji {
+  const EXPECTED: &'static str = "expected a custom emoji: <:name:id>";
 
-    let guild = params.message.guild_id.unwrap_or(GuildId(GUILD_ID));
-    guild
-      .create_emoji(&params.context.http, emoji_name, &base64_img)
-      .await?;
-    Ok(Some(String::from(":ok:")))
-  } else {
-    Ok(Some(String::from("I am not able to get this emoji")))
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    emoji_str_convert(argument)
+      .map(|(animated, name, id)| CustomEmoji {
+        animated,
+        name: name.to_string(),
+        id: id.to_string(),
+      })
+      .ok_or_else(|| String::from(Self::EXPECTED))
   }
 }
+
+#[command(
+  name = "emoji-add",
+  usage = "@BOT emoji-add <custom emoji>",
+  min_args = 1,
+  permission = User
+)]
+pub async fn add(params: CallBackParams<'_>) -> CallbackReturn {
+  let emoji: CustomEmoji = params.arguments().next()?;
+  let extension = if emoji.animated { "gif" } else { "png" };
+  let url = format!(
+    "https://cdn.discordapp.com/emojis/{}.{}?size=128",
+    emoji.id, extension
+  );
+
+  let client = reqwest::Client::builder().build()?;
+  let response = client.get(url).send().await.unwrap();
+  let response_body = response.bytes().await.unwrap();
+  let base64_img = format!(
+    "data:image/{};base64,{}",
+    extension,
+    base64::encode(response_body)
+  );
+
+  let guild = match parse::get_guild(params.message.channel_id, params.context, None).await {
+    Ok(guild) => guild,
+    Err(error) => return Ok(Some(error)),
+  };
+  guild
+    .create_emoji(&params.context.http, &emoji.name, &base64_img)
+    .await?;
+  Ok(Some(String::from(":ok:")))
+}
@@ -0,0 +1,322 @@
+//! Parse the date expressions given to remindme, in english or french:
+//! - durations: `10m`, `1h30m`, `in 2 weeks`, `dans 3 jours`
+//! - days: `today`, `tomorrow`, `demain`, `après-demain`, `friday`, `vendredi`
+//! - dates: `25/12`, `25/12/2022`, `2022-12-25`
+//! - times: `9am`, `14h`, `14h30`, `14:30`, `noon`, `midi`
+//!
+//! A day and a time can be combined: `tomorrow 9am`, `vendredi à 14h`, `25/12 10:00`.
+use crate::core::parse::duration_from_str;
+use chrono::{
+  DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday,
+};
+use chrono_tz::Tz;
+use regex::Regex;
+
+/// Longest expression looked for at the start of the arguments
+const MAX_WORDS: usize = 5;
+
+/// Time used when only the day is given
+const DEFAULT_HOUR: u32 = 9;
+
+const WEEKDAYS: [(&str, &str, Weekday); 7] = [
+  ("monday", "lundi", Weekday::Mon),
+  ("tuesday", "mardi", Weekday::Tue),
+  ("wednesday", "mercredi", Weekday::Wed),
+  ("thursday", "jeudi", Weekday::Thu),
+  ("friday", "vendredi", Weekday::Fri),
+  ("saturday", "samedi", Weekday::Sat),
+  ("sunday", "dimanche", Weekday::Sun),
+];
+
+/// Words ignored between the parts of an expression: `next friday at 9am`, `vendredi prochain à 9h`
+const FILLERS: [&str; 11] = [
+  "in", "dans", "at", "à", "a", "on", "le", "next", "prochain", "this", "ce",
+];
+
+lazy_static! {
+  static ref TIME: Regex =
+    Regex::new(r#"^([0-9]{1,2})(?:([:h])([0-9]{2})?)?(am|pm)?$"#).expect("unable to create regex");
+  /// Kept from the first version of remindme: `2d10:30` is in 2 days at 10:30
+  static ref DAYS_AT: Regex =
+    Regex::new(r#"^([0-9]{1,3})d(?:ays?)?([0-9]{2})[:h]([0-9]{2})?$"#).expect("unable to create regex");
+}
+
+/// The date of the local time in `timezone`, a time skipped by the change to summer time happens an hour later
+pub fn resolve_local(timezone: Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+  timezone.from_local_datetime(&local).earliest().or_else(|| {
+    timezone
+      .from_local_datetime(&(local + Duration::hours(1)))
+      .earliest()
+  })
+}
+
+/// Days are added to the local date so the time doesn't move with the daylight saving
+fn add_duration(now: DateTime<Tz>, duration: Duration) -> Option<DateTime<Tz>> {
+  let days = Duration::days(duration.num_days());
+  let local = resolve_local(now.timezone(), now.naive_local() + days)?;
+  Some(local + (duration - days))
+}
+
+fn parse_time(word: &str) -> Option<NaiveTime> {
+  match word {
+    "noon" | "midi" => return NaiveTime::from_hms_opt(12, 0, 0),
+    "midnight" | "minuit" => return NaiveTime::from_hms_opt(0, 0, 0),
+    _ => {}
+  }
+  let captures = TIME.captures(word)?;
+  // A lone number isn't a time
+  if captures.get(2).is_none() && captures.get(4).is_none() {
+    return None;
+  }
+  let mut hour: u32 = captures[1].parse().ok()?;
+  let minute: u32 = captures
+    .get(3)
+    .map_or(Some(0), |m| m.as_str().parse().ok())?;
+  match captures.get(4).map(|m| m.as_str()) {
+    Some(_) if hour == 0 || hour > 12 => return None,
+    Some("am") if hour == 12 => hour = 0,
+    Some("pm") if hour != 12 => hour += 12,
+    _ => {}
+  }
+  NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+  WEEKDAYS
+    .iter()
+    .find(|(english, french, _)| *english == word || english[..3] == *word || *french == word)
+    .map(|(_, _, weekday)| *weekday)
+}
+
+enum Day {
+  /// today, tomorrow or a full date
+  Exact(NaiveDate),
+  /// The next one if it's already past
+  Weekday(NaiveDate),
+  /// Date without a year, the next year if it's already past
+  WithoutYear(NaiveDate),
+}
+
+fn parse_day(word: &str, today: NaiveDate) -> Option<Day> {
+  match word {
+    "today" | "aujourd'hui" | "aujourdhui" => return Some(Day::Exact(today)),
+    "tomorrow" | "demain" => return Some(Day::Exact(today.succ_opt()?)),
+    "après-demain" | "apres-demain" => return Some(Day::Exact(today + Duration::days(2))),
+    _ => {}
+  }
+  if let Some(weekday) = parse_weekday(word) {
+    let days_ahead =
+      (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    return Some(Day::Weekday(today + Duration::days(days_ahead.into())));
+  }
+  if let Ok(date) = NaiveDate::parse_from_str(word, "%d/%m/%Y") {
+    return Some(Day::Exact(date));
+  }
+  if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+    return Some(Day::Exact(date));
+  }
+  NaiveDate::parse_from_str(&format!("{}/{}", word, today.year()), "%d/%m/%Y")
+    .ok()
+    .map(Day::WithoutYear)
+}
+
+/// Parse a whole expression, `None` if it isn't a date or if it's already past
+pub fn parse_date(words: &[&str], now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+  let words: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+  if words.is_empty() || FILLERS.contains(&words[words.len() - 1].as_str()) {
+    return None;
+  }
+  // `14h` is in 14 hours unless it's written `at 14h`
+  let is_duration = matches!(words[0].as_str(), "in" | "dans");
+  let is_time = words
+    .iter()
+    .any(|word| ["at", "à", "a"].contains(&word.as_str()));
+  let words: Vec<&str> = words
+    .iter()
+    .map(String::as_str)
+    .filter(|word| !FILLERS.contains(word))
+    .collect();
+
+  if let [word] = words.as_slice() {
+    if let Some(captures) = DAYS_AT.captures(word) {
+      let days: i64 = captures[1].parse().ok()?;
+      let time = NaiveTime::from_hms_opt(
+        captures[2].parse().ok()?,
+        captures
+          .get(3)
+          .map_or(Some(0), |m| m.as_str().parse().ok())?,
+        0,
+      )?;
+      let date = now.naive_local().date() + Duration::days(days);
+      return resolve_local(now.timezone(), date.and_time(time)).filter(|date| *date > now);
+    }
+  }
+  // Numbers and units can be separated: `2 weeks`, `1h 30m`
+  if is_duration || !is_time {
+    let duration = duration_from_str(&words.concat());
+    if is_duration || duration.is_some() {
+      return add_duration(now, duration?);
+    }
+  }
+
+  let today = now.naive_local().date();
+  let (day, time_words) = match words.split_first() {
+    Some((first, rest)) => match parse_day(first, today) {
+      Some(day) => (Some(day), rest),
+      None => (None, words.as_slice()),
+    },
+    None => return None,
+  };
+  // `9 am` or `14 h 30` are written with spaces
+  let time = match time_words {
+    [] => None,
+    time_words => Some(parse_time(&time_words.concat())?),
+  };
+
+  let (date, next) = match day {
+    Some(Day::Exact(date)) => (date, None),
+    Some(Day::Weekday(date)) => (date, date.checked_add_signed(Duration::weeks(1))),
+    Some(Day::WithoutYear(date)) => (date, date.with_year(date.year() + 1)),
+    None => (today, today.succ_opt()),
+  };
+  let time = match (time, day.is_some()) {
+    (Some(time), _) => time,
+    (None, true) => NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0)?,
+    (None, false) => return None,
+  };
+  let timezone = now.timezone();
+  match resolve_local(timezone, date.and_time(time)) {
+    Some(date) if date > now => Some(date),
+    _ => resolve_local(timezone, next?.and_time(time)).filter(|date| *date > now),
+  }
+}
+
+/// Find the longest date expression at the start of `words`, returns the date and the number of words used
+pub fn parse_date_prefix(words: &[&str], now: DateTime<Tz>) -> Option<(DateTime<Tz>, usize)> {
+  (1..=words.len().min(MAX_WORDS))
+    .rev()
+    .find_map(|count| Some((parse_date(&words[..count], now)?, count)))
+}
+
+#[test]
+fn test_parse_date() {
+  use chrono_tz::Europe::Paris;
+
+  // Friday 28/10/2022 10:00
+  let now = Paris.ymd(2022, 10, 28).and_hms(10, 0, 0);
+  let parse = |expression: &str| {
+    let words: Vec<&str> = expression.split_whitespace().collect();
+    parse_date(&words, now)
+  };
+
+  assert_eq!(parse("10m"), Some(now + Duration::minutes(10)));
+  assert_eq!(parse("1h30m"), Some(now + Duration::minutes(90)));
+  assert_eq!(
+    parse("in 2 weeks"),
+    Some(Paris.ymd(2022, 11, 11).and_hms(10, 0, 0))
+  );
+  assert_eq!(
+    parse("dans 3 jours"),
+    Some(Paris.ymd(2022, 10, 31).and_hms(10, 0, 0))
+  );
+  assert_eq!(
+    parse("2d10:30"),
+    Some(Paris.ymd(2022, 10, 30).and_hms(10, 30, 0))
+  );
+  assert_eq!(
+    parse("tomorrow 9am"),
+    Some(Paris.ymd(2022, 10, 29).and_hms(9, 0, 0))
+  );
+  assert_eq!(
+    parse("demain à 14h30"),
+    Some(Paris.ymd(2022, 10, 29).and_hms(14, 30, 0))
+  );
+  assert_eq!(
+    parse("friday 14h"),
+    Some(Paris.ymd(2022, 10, 28).and_hms(14, 0, 0))
+  );
+  assert_eq!(
+    parse("Vendredi 8h"),
+    Some(Paris.ymd(2022, 11, 4).and_hms(8, 0, 0))
+  );
+  assert_eq!(
+    parse("next monday"),
+    Some(Paris.ymd(2022, 10, 31).and_hms(9, 0, 0))
+  );
+  assert_eq!(
+    parse("25/12 10:00"),
+    Some(Paris.ymd(2022, 12, 25).and_hms(10, 0, 0))
+  );
+  assert_eq!(parse("01/02"), Some(Paris.ymd(2023, 2, 1).and_hms(9, 0, 0)));
+  assert_eq!(
+    parse("2023-01-15 noon"),
+    Some(Paris.ymd(2023, 1, 15).and_hms(12, 0, 0))
+  );
+  assert_eq!(
+    parse("9:30 pm"),
+    Some(Paris.ymd(2022, 10, 28).and_hms(21, 30, 0))
+  );
+  assert_eq!(parse("8h"), Some(now + Duration::hours(8)));
+  assert_eq!(
+    parse("at 8h"),
+    Some(Paris.ymd(2022, 10, 29).and_hms(8, 0, 0))
+  );
+  assert_eq!(
+    parse("12am"),
+    Some(Paris.ymd(2022, 10, 29).and_hms(0, 0, 0))
+  );
+  assert_eq!(parse("today 8h"), None);
+  assert_eq!(parse("25/12/2021"), None);
+  assert_eq!(parse("13pm"), None);
+  assert_eq!(parse("9"), None);
+  assert_eq!(parse("tomorrow at"), None);
+}
+
+#[test]
+fn test_parse_date_dst() {
+  use chrono_tz::Europe::Paris;
+
+  // Winter time starts on the 30/10/2022 at 03:00 CEST, going back to 02:00 CET
+  let before_winter = Paris.ymd(2022, 10, 29).and_hms(20, 0, 0);
+  let parse = |expression: &str, now: DateTime<Tz>| {
+    let words: Vec<&str> = expression.split_whitespace().collect();
+    parse_date(&words, now).map(|date| date.naive_utc())
+  };
+  let utc = |day: u32, month: u32, hour: u32, minute: u32| {
+    Some(NaiveDate::from_ymd(2022, month, day).and_hms(hour, minute, 0))
+  };
+
+  assert_eq!(parse("tomorrow 9am", before_winter), utc(30, 10, 8, 0));
+  // A day later is at the same local time, 25 hours later
+  assert_eq!(parse("1d", before_winter), utc(30, 10, 19, 0));
+  assert_eq!(parse("at 8h", before_winter), utc(30, 10, 7, 0));
+  // Hours are real hours
+  assert_eq!(parse("in 10h", before_winter), utc(30, 10, 4, 0));
+  // 02:30 happens twice, the first one is used
+  assert_eq!(parse("30/10 02:30", before_winter), utc(30, 10, 0, 30));
+
+  // Summer time starts on the 27/03/2022 at 02:00 CET, going to 03:00 CEST
+  let before_summer = Paris.ymd(2022, 3, 26).and_hms(20, 0, 0);
+  assert_eq!(parse("demain 9h", before_summer), utc(27, 3, 7, 0));
+  // 02:30 doesn't exist, it's moved an hour later
+  assert_eq!(parse("27/03 2h30", before_summer), utc(27, 3, 1, 30));
+  assert_eq!(parse("in 1 day", before_summer), utc(27, 3, 18, 0));
+}
+
+#[test]
+fn test_parse_date_prefix() {
+  use chrono_tz::Europe::Paris;
+
+  let now = Paris.ymd(2022, 10, 28).and_hms(10, 0, 0);
+  let words = ["tomorrow", "at", "9am", "buy", "some", "bread"];
+
+  assert_eq!(
+    parse_date_prefix(&words, now),
+    Some((Paris.ymd(2022, 10, 29).and_hms(9, 0, 0), 3))
+  );
+  assert_eq!(
+    parse_date_prefix(&["10m", "a", "b"], now),
+    Some((now + Duration::minutes(10), 1))
+  );
+  assert_eq!(parse_date_prefix(&["buy", "bread"], now), None);
+}
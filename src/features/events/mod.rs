@@ -0,0 +1,425 @@
+use super::recurrence::Recurrence;
+use crate::{
+  core::{
+    commands::{CallBackParams, CallbackReturn},
+    timezone::{format_date, user_now},
+  },
+  database::{Delivery, Event, NewEvent, INSTANCE},
+};
+use chrono::prelude::*;
+use chrono_tz::Tz;
+use procedural_macros::command;
+use serenity::{
+  builder::CreateComponents,
+  client::Context,
+  model::{
+    application::{
+      component::ButtonStyle,
+      interaction::{message_component::MessageComponentInteraction, InteractionResponseType},
+    },
+    id::{ChannelId, UserId},
+  },
+  prelude::Mentionable,
+};
+
+pub mod dateparse;
+pub mod scheduler;
+
+const INVALID_DATE: &str =
+  "the time parameter is invalid or already past, ex: 10m, 1h30m, tomorrow 9am, friday 14h, 25/12 10:00, in 2 weeks";
+
+/// The text of `arg` after its first `count` words
+fn skip_words(arg: &str, count: usize) -> &str {
+  let mut rest = arg.trim_start();
+  for _ in 0..count {
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    rest = rest[end..].trim_start();
+  }
+  rest
+}
+
+/// Split the arguments in the date expression and the content of the reminder, the
+/// content is kept as written so a quoted one keeps its lines and indentation
+fn parse_date_and_content(
+  args: &[String],
+  now: DateTime<Tz>,
+) -> Result<(DateTime<Tz>, String), String> {
+  // A quoted expression is a single argument
+  let words: Vec<&str> = args.iter().flat_map(|arg| arg.split_whitespace()).collect();
+  let (trigger_date, mut count) = match dateparse::parse_date_prefix(&words, now) {
+    Some(result) => result,
+    None => return Err(INVALID_DATE.to_string()),
+  };
+  let mut content: Vec<&str> = Vec::new();
+  for arg in args {
+    let arg_words = arg.split_whitespace().count();
+    if count >= arg_words {
+      count -= arg_words;
+      continue;
+    }
+    content.push(skip_words(arg, count));
+    count = 0;
+  }
+  let content = content.join(" ");
+  if content.is_empty() {
+    return Err("The content of the reminder is missing".to_string());
+  }
+  if content.len() > CONTENT_MAX_LEN {
+    return Err("Your message is too long".to_string());
+  }
+  Ok((trigger_date, content))
+}
+
+/// Flag of remindme to receive the reminder by direct message
+const DM_FLAG: &str = "--dm";
+
+/// Add the reminder described by the arguments `<WHEN> <CONTENT>`, `--dm` can be anywhere.
+/// Reminders created in a direct message are sent there.
+fn add_reminder(params: &CallBackParams, delivery: Delivery) -> String {
+  let written = params.arguments().rest();
+  let args: Vec<String> = written
+    .iter()
+    .filter(|arg| *arg != DM_FLAG)
+    .cloned()
+    .collect();
+  let delivery = if args.len() < written.len() || params.message.is_private() {
+    Delivery::Dm
+  } else {
+    delivery
+  };
+  let now = user_now(params.message.author.id);
+  let (trigger_date, content) = match parse_date_and_content(&args, now) {
+    Ok(result) => result,
+    Err(error) => return error,
+  };
+  let mut db_instance = INSTANCE.write().unwrap();
+  db_instance.event_add(NewEvent {
+    author: params.message.author.id.0 as i64,
+    channel: params.message.channel_id.0 as i64,
+    content: &content,
+    trigger_date: trigger_date.naive_utc(),
+    recurrence: None,
+    delivery: &delivery.to_string(),
+    project_id: None,
+  });
+  scheduler::reschedule();
+  ":ok:".to_string()
+}
+
+#[command(
+  name = "remindme",
+  usage = "@BOT remindme [--dm] <WHEN ex: 10m, 1h30m, tomorrow 9am, friday 14h, 25/12 10:00> <CONTENT>",
+  min_args = 2,
+  max_args = 100,
+  direct_message = true,
+  permission = User
+)]
+pub async fn remind_me(params: CallBackParams) -> CallbackReturn {
+  Ok(Some(add_reminder(&params, Delivery::Channel)))
+}
+
+#[command(
+  name = "remindme-private",
+  usage = "@BOT remindme-private <WHEN ex: 10m, 1h30m, tomorrow 9am, friday 14h> <CONTENT>",
+  min_args = 2,
+  max_args = 100,
+  direct_message = true,
+  permission = User
+)]
+pub async fn remind_me_private(params: CallBackParams) -> CallbackReturn {
+  Ok(Some(add_reminder(&params, Delivery::Dm)))
+}
+
+#[command(
+  name = "schedule",
+  usage = "@BOT schedule \"<every day|weekday|monday|first monday at HH:MM|cron m h dom mon dow>\" <CONTENT> [<#channel>]",
+  min_args = 2,
+  max_args = 3,
+  permission = User
+)]
+pub async fn schedule(params: CallBackParams) -> CallbackReturn {
+  let mut arguments = params.arguments();
+  let recurrence: Recurrence = arguments.next()?;
+  let content: String = arguments.next()?;
+  let channel = arguments
+    .optional::<ChannelId>()?
+    .unwrap_or(params.message.channel_id);
+  if content.len() > CONTENT_MAX_LEN {
+    return Ok(Some("Your message is too long".to_string()));
+  }
+  let trigger_date = match recurrence.next_after(user_now(params.message.author.id)) {
+    Some(trigger_date) => trigger_date,
+    None => return Ok(Some("This recurrence never happens".to_string())),
+  };
+  let rule = recurrence.to_string();
+  let mut db_instance = INSTANCE.write().unwrap();
+  db_instance.event_add(NewEvent {
+    author: params.message.author.id.0 as i64,
+    channel: channel.0 as i64,
+    content: &content,
+    trigger_date: trigger_date.naive_utc(),
+    recurrence: Some(&rule),
+    delivery: &Delivery::Channel.to_string(),
+    project_id: None,
+  });
+  scheduler::reschedule();
+  Ok(Some(format!(
+    "Scheduled {}, next on {}",
+    rule,
+    format_date(trigger_date.naive_utc(), params.message.author.id)
+  )))
+}
+
+#[command(
+  name = "reminders",
+  usage = "@BOT reminders",
+  direct_message = true,
+  permission = User
+)]
+pub async fn reminders(params: CallBackParams) -> CallbackReturn {
+  let db_instance = INSTANCE.read().unwrap();
+  let mut events: Vec<&Event> = db_instance
+    .events
+    .iter()
+    .filter(|event| event.author == params.message.author.id.0 as i64)
+    .collect();
+  if events.is_empty() {
+    return Ok(Some(String::from("You don't have any pending reminder")));
+  }
+  events.sort_by_key(|event| event.trigger_date);
+
+  let mut list = String::new();
+  for event in events {
+    let recurrence = match &event.recurrence {
+      Some(rule) => format!(" ({})", rule),
+      None => String::new(),
+    };
+    let line = format!(
+      "`#{}` {}{} in {}: {}\n",
+      event.id,
+      format_date(event.trigger_date, params.message.author.id),
+      recurrence,
+      ChannelId(event.channel as u64).mention(),
+      event.content.chars().take(100).collect::<String>(),
+    );
+    if list.len() + line.len() > CONTENT_MAX_LEN {
+      list.push_str("...");
+      break;
+    }
+    list.push_str(&line);
+  }
+  Ok(Some(list))
+}
+
+/// Only the author of a reminder can change it
+fn find_own_event(event_id: i32, author: UserId) -> Result<Event, String> {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .events
+    .iter()
+    .find(|event| event.id == event_id && event.author == author.0 as i64)
+    .cloned()
+    .ok_or_else(|| format!("Reminder #{} not found", event_id))
+}
+
+#[command(
+  name = "reminder-cancel",
+  usage = "@BOT reminder-cancel <id>",
+  min_args = 1,
+  direct_message = true,
+  permission = User
+)]
+pub async fn reminder_cancel(params: CallBackParams) -> CallbackReturn {
+  let event_id: i32 = params.arguments().next()?;
+  let event = match find_own_event(event_id, params.message.author.id) {
+    Ok(event) => event,
+    Err(error) => return Ok(Some(error)),
+  };
+  let mut db_instance = INSTANCE.write().unwrap();
+  db_instance.event_delete(event.id);
+  scheduler::reschedule();
+  Ok(Some(":ok:".to_string()))
+}
+
+#[command(
+  name = "reminder-edit",
+  usage = "@BOT reminder-edit <id> <WHEN ex: 10m, tomorrow 9am, friday 14h> <CONTENT>",
+  min_args = 3,
+  max_args = 100,
+  direct_message = true,
+  permission = User
+)]
+pub async fn reminder_edit(params: CallBackParams) -> CallbackReturn {
+  let mut arguments = params.arguments();
+  let event_id: i32 = arguments.next()?;
+  let now = user_now(params.message.author.id);
+  let (trigger_date, content) = match parse_date_and_content(arguments.rest(), now) {
+    Ok(result) => result,
+    Err(error) => return Ok(Some(error)),
+  };
+  let event = match find_own_event(event_id, params.message.author.id) {
+    Ok(event) => event,
+    Err(error) => return Ok(Some(error)),
+  };
+  let mut db_instance = INSTANCE.write().unwrap();
+  db_instance.event_update(event.id, trigger_date.naive_utc(), &content);
+  scheduler::reschedule();
+  Ok(Some(format!(
+    "Reminder #{} set for {}",
+    event.id,
+    format_date(trigger_date.naive_utc(), params.message.author.id)
+  )))
+}
+
+/// Custom id of the snooze buttons: `snooze:<delay>:<author>`
+pub const SNOOZE_ID: &str = "snooze";
+const SNOOZE_DELAYS: [(&str, &str); 3] = [
+  ("10m", "10 minutes"),
+  ("1h", "1 hour"),
+  ("tomorrow", "Tomorrow"),
+];
+
+/// The delays are date expressions, `tomorrow` being tomorrow morning
+fn snooze_date(delay: &str, now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+  if !SNOOZE_DELAYS
+    .iter()
+    .any(|(snooze_delay, _)| *snooze_delay == delay)
+  {
+    return None;
+  }
+  dateparse::parse_date(&[delay], now)
+}
+
+fn snooze_buttons(components: &mut CreateComponents, author: i64) -> &mut CreateComponents {
+  components.create_action_row(|row| {
+    for (delay, label) in SNOOZE_DELAYS {
+      row.create_button(|button| {
+        button
+          .custom_id(format!("{}:{}:{}", SNOOZE_ID, delay, author))
+          .label(format!("Snooze {}", label))
+          .style(ButtonStyle::Secondary)
+      });
+    }
+    row
+  })
+}
+
+/// Add the reminder again when the author clicks one of the snooze buttons
+pub async fn snooze(ctx: &Context, component: &MessageComponentInteraction) {
+  let mut custom_id = component.data.custom_id.split(':').skip(1);
+  let (delay, author) = match (
+    custom_id.next(),
+    custom_id
+      .next()
+      .and_then(|author| author.parse::<u64>().ok()),
+  ) {
+    (Some(delay), Some(author)) => (delay, UserId(author)),
+    _ => return error!("Invalid snooze button {}", component.data.custom_id),
+  };
+  if component.user.id != author {
+    let result = component
+      .create_interaction_response(&ctx.http, |res| {
+        res
+          .kind(InteractionResponseType::ChannelMessageWithSource)
+          .interaction_response_data(|data| {
+            data
+              .content(format!(
+                "Only {} can snooze this reminder",
+                author.mention()
+              ))
+              .ephemeral(true)
+          })
+      })
+      .await;
+    if let Err(why) = result {
+      error!("Unable to respond to snooze: {}", why);
+    }
+    return;
+  }
+  let trigger_date = match snooze_date(delay, user_now(author)) {
+    Some(trigger_date) => trigger_date,
+    None => return error!("Invalid snooze delay {}", delay),
+  };
+
+  // The reminder was sent as "<@author> content"
+  let message = &component.message.content;
+  let mention = format!("{} ", author.mention());
+  let content = message.strip_prefix(&mention).unwrap_or(message);
+  // The buttons are in a direct message when the reminder was sent there
+  let delivery = match component.guild_id {
+    Some(_) => Delivery::Channel,
+    None => Delivery::Dm,
+  };
+  {
+    let mut db_instance = INSTANCE.write().unwrap();
+    db_instance.event_add(NewEvent {
+      author: author.0 as i64,
+      channel: component.channel_id.0 as i64,
+      content,
+      trigger_date: trigger_date.naive_utc(),
+      recurrence: None,
+      delivery: &delivery.to_string(),
+      project_id: None,
+    });
+  }
+  scheduler::reschedule();
+  let result = component
+    .create_interaction_response(&ctx.http, |res| {
+      res
+        .kind(InteractionResponseType::UpdateMessage)
+        .interaction_response_data(|data| {
+          data
+            .content(format!(
+              "{}\n*Snoozed until {}*",
+              message,
+              format_date(trigger_date.naive_utc(), author)
+            ))
+            .components(|components| components)
+        })
+    })
+    .await;
+  if let Err(why) = result {
+    error!("Unable to respond to snooze: {}", why);
+  }
+}
+
+/// Keep some room for the mention of the author
+const CONTENT_MAX_LEN: usize = 1900;
+
+#[test]
+fn test_parse_date_and_content() {
+  use chrono_tz::Europe::Paris;
+
+  let now = Paris.ymd(2022, 10, 28).and_hms(10, 0, 0);
+  let args = |args: &[&str]| {
+    args
+      .iter()
+      .map(|arg| String::from(*arg))
+      .collect::<Vec<_>>()
+  };
+
+  let (date, content) =
+    parse_date_and_content(&args(&["10m", "- first\n  - second", "end"]), now).unwrap();
+  assert_eq!(date, now + chrono::Duration::minutes(10));
+  assert_eq!(content, "- first\n  - second end");
+  let (_, content) = parse_date_and_content(&args(&["tomorrow 9am call\n  Bob"]), now).unwrap();
+  assert_eq!(content, "call\n  Bob");
+  assert!(parse_date_and_content(&args(&["tomorrow", "9am"]), now).is_err());
+}
+
+#[test]
+fn test_snooze_date() {
+  use chrono_tz::Europe::Paris;
+
+  let now = Paris.ymd(2022, 10, 30).and_hms(18, 42, 10);
+
+  assert_eq!(
+    snooze_date("1h", now).unwrap(),
+    Paris.ymd(2022, 10, 30).and_hms(19, 42, 10)
+  );
+  assert_eq!(
+    snooze_date("tomorrow", now).unwrap(),
+    Paris.ymd(2022, 10, 31).and_hms(9, 0, 0)
+  );
+  assert!(snooze_date("2h", now).is_none());
+}
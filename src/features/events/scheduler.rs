@@ -0,0 +1,230 @@
+//! Send the events at their trigger date.
+//!
+//! The upcoming events are kept in a heap and the scheduler sleeps until the first one,
+//! [`reschedule`] wakes it up when the events are changed.
+//!
+//! Events missed while the bot was down are handled according to `EVENTS_CATCH_UP`:
+//! - `all` (default): they are all sent
+//! - `skip`: they are dropped, a recurring event still moves to its next occurrence
+//! - a duration like `12h`: only the events missed for less than that are sent
+use super::snooze_buttons;
+use crate::{
+  core::{parse, timezone::user_timezone},
+  database::{Delivery, Event, INSTANCE},
+  features::recurrence::Recurrence,
+};
+use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+use serenity::{
+  http::Http,
+  model::{
+    channel::Message,
+    id::{ChannelId, UserId},
+  },
+  prelude::Mentionable,
+};
+use std::{cmp::Reverse, collections::BinaryHeap, env, str::FromStr, sync::Arc};
+use tokio::{sync::Notify, time};
+
+lazy_static! {
+  static ref NOTIFY: Notify = Notify::new();
+  static ref CATCH_UP: CatchUp = match env::var("EVENTS_CATCH_UP") {
+    Ok(policy) => policy.parse().unwrap_or_else(|why| {
+      error!("{}", why);
+      CatchUp::All
+    }),
+    Err(_) => CatchUp::All,
+  };
+}
+
+/// An event sent later than this was missed
+const LATE_TOLERANCE_SECS: i64 = 60;
+/// The queue is checked at least this often in case the clock changes
+const MAX_SLEEP_SECS: i64 = 3600;
+
+#[derive(Debug, PartialEq)]
+pub enum CatchUp {
+  All,
+  Skip,
+  Within(Duration),
+}
+
+impl FromStr for CatchUp {
+  type Err = String;
+
+  fn from_str(policy: &str) -> Result<Self, Self::Err> {
+    match policy {
+      "all" => Ok(CatchUp::All),
+      "skip" => Ok(CatchUp::Skip),
+      _ => parse::duration_from_str(policy)
+        .map(CatchUp::Within)
+        .ok_or_else(|| {
+          format!(
+            "Invalid EVENTS_CATCH_UP {}, expected all, skip or a duration",
+            policy
+          )
+        }),
+    }
+  }
+}
+
+impl CatchUp {
+  /// Whether an event triggered `late` after its date is still sent
+  fn should_send(&self, late: Duration) -> bool {
+    if late <= Duration::seconds(LATE_TOLERANCE_SECS) {
+      return true;
+    }
+    match self {
+      CatchUp::All => true,
+      CatchUp::Skip => false,
+      CatchUp::Within(max) => late <= *max,
+    }
+  }
+}
+
+/// Has to be called when an event is added, edited or removed
+pub fn reschedule() {
+  NOTIFY.notify_one();
+}
+
+/// Trigger dates of the events with their id, the earliest first
+fn load_queue() -> BinaryHeap<Reverse<(NaiveDateTime, i32)>> {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .events
+    .iter()
+    .map(|event| Reverse((event.trigger_date, event.id)))
+    .collect()
+}
+
+/// Send the event by direct message if it was asked, in its channel otherwise
+/// or when the author doesn't accept direct messages.
+/// A recurring event is a scheduled post, only its content is sent.
+async fn send(http: &Arc<Http>, event: &Event) -> serenity::Result<Message> {
+  let author = UserId(event.author as u64);
+  let recurring = event.recurrence.is_some();
+  if event.delivery == Delivery::Dm.to_string() {
+    let result = match author.create_dm_channel(http).await {
+      Ok(channel) => {
+        channel
+          .send_message(http, |message| {
+            message.content(&event.content);
+            if !recurring {
+              message.components(|components| snooze_buttons(components, event.author));
+            }
+            message
+          })
+          .await
+      }
+      Err(why) => Err(why),
+    };
+    match result {
+      Ok(message) => return Ok(message),
+      Err(why) => warn!(
+        "Unable to send event {} by direct message: {}",
+        event.id, why
+      ),
+    }
+  }
+  ChannelId(event.channel as u64)
+    .send_message(http, |message| {
+      if recurring {
+        message.content(&event.content)
+      } else {
+        message
+          .content(format!("{} {}", author.mention(), event.content))
+          .components(|components| snooze_buttons(components, event.author))
+      }
+    })
+    .await
+}
+
+/// Send the event, then move it to its next occurrence or delete it
+async fn trigger(http: &Arc<Http>, event: Event, now: NaiveDateTime) -> Option<NaiveDateTime> {
+  let author = UserId(event.author as u64);
+  let next_trigger = event
+    .recurrence
+    .as_ref()
+    .and_then(|rule| match rule.parse::<Recurrence>() {
+      Ok(recurrence) => recurrence
+        .next_after(user_timezone(author).from_utc_datetime(&now))
+        .map(|next_trigger| next_trigger.naive_utc()),
+      Err(why) => {
+        error!("Invalid recurrence of event {}: {}", event.id, why);
+        None
+      }
+    });
+
+  if CATCH_UP.should_send(now - event.trigger_date) {
+    if let Err(why) = send(http, &event).await {
+      error!("Unable to send event {}: {}", event.id, why);
+    }
+  } else {
+    info!(
+      "Skipping event {} missed since {}",
+      event.id, event.trigger_date
+    );
+  }
+
+  let mut db_instance = INSTANCE.write().unwrap();
+  match next_trigger {
+    Some(next_trigger) => db_instance.event_update(event.id, next_trigger, &event.content),
+    None => db_instance.event_delete(event.id),
+  }
+  next_trigger
+}
+
+pub async fn run(http: Arc<Http>) {
+  info!("running events scheduler");
+  let mut queue = load_queue();
+  loop {
+    // Dates are stored as utc in the database
+    let now = Utc::now().naive_utc();
+    while let Some(Reverse((trigger_date, event_id))) = queue.peek().copied() {
+      if trigger_date > now {
+        break;
+      }
+      queue.pop();
+      // The event may have been edited or removed since it was queued
+      let event = {
+        let db_instance = INSTANCE.read().unwrap();
+        db_instance
+          .events
+          .iter()
+          .find(|event| event.id == event_id && event.trigger_date == trigger_date)
+          .cloned()
+      };
+      if let Some(event) = event {
+        if let Some(next_trigger) = trigger(&http, event, now).await {
+          queue.push(Reverse((next_trigger, event_id)));
+        }
+      }
+    }
+
+    let sleep = match queue.peek() {
+      Some(Reverse((trigger_date, _))) => {
+        (*trigger_date - now).min(Duration::seconds(MAX_SLEEP_SECS))
+      }
+      None => Duration::seconds(MAX_SLEEP_SECS),
+    };
+    tokio::select! {
+      _ = time::sleep(sleep.to_std().unwrap_or_default()) => {}
+      _ = NOTIFY.notified() => queue = load_queue(),
+    }
+  }
+}
+
+#[test]
+fn test_catch_up() {
+  assert_eq!(CatchUp::from_str("skip").unwrap(), CatchUp::Skip);
+  assert_eq!(
+    CatchUp::from_str("12h").unwrap(),
+    CatchUp::Within(Duration::hours(12))
+  );
+  assert!(CatchUp::from_str("sometimes").is_err());
+
+  assert!(CatchUp::Skip.should_send(Duration::seconds(30)));
+  assert!(!CatchUp::Skip.should_send(Duration::minutes(5)));
+  assert!(CatchUp::All.should_send(Duration::days(3)));
+  assert!(CatchUp::Within(Duration::hours(12)).should_send(Duration::hours(2)));
+  assert!(!CatchUp::Within(Duration::hours(12)).should_send(Duration::days(1)));
+}
@@ -2,28 +2,41 @@ use std::time::SystemTime;
 
 use crate::core::commands::{CallBackParams, CallbackReturn};
 use crate::database;
-use chrono::{DateTime, Utc};
 use database::{NewStorage, StorageDataType};
 use procedural_macros::command;
 use serde_json::{from_str, Value};
-use serenity::prelude::*;
+use serenity::{model::id::UserId, prelude::*};
 
 lazy_static! {
   pub static ref ATTACKED: RwLock<String> = RwLock::new(String::new());
 }
 
-#[command]
+#[command(
+  name = "attack",
+  usage = "@BOT attack <@user>",
+  min_args = 1,
+  permission = User
+)]
 pub async fn attack_lauch(params: CallBackParams) -> CallbackReturn {
+  let user_id: UserId = params.arguments().next()?;
   ATTACKED.write().await.clear();
 
-  let tag = format!("<@{}", &params.args[1][3..]);
+  let tag = user_id.mention().to_string();
   ATTACKED.write().await.push_str(&tag);
-  Ok(Some(format!("Prepare yourself {} !", params.args[1])))
+  Ok(Some(format!("Prepare yourself {} !", tag)))
 }
 
-pub async fn mom_change_cmdless(user: &str, timestamp: DateTime<Utc>) -> Option<String> {
+#[command(
+  name = "mom-change",
+  aliases = ["momchange"],
+  usage = "@BOT mom-change <@user>",
+  min_args = 1,
+  permission = User
+)]
+pub async fn mom_change(params: CallBackParams) -> CallbackReturn {
+  let user = &params.arguments().next::<UserId>()?.mention().to_string();
   let mut db_instance = database::INSTANCE.write().unwrap();
-  let time: SystemTime = SystemTime::from(timestamp);
+  let time: SystemTime = SystemTime::from(*params.message.timestamp);
   let storage_found = db_instance.find_storage_type(StorageDataType::Mom).cloned();
   if let Some(storage) = storage_found {
     db_instance.storage_update(storage.id, user);
@@ -35,30 +48,29 @@ pub async fn mom_change_cmdless(user: &str, timestamp: DateTime<Utc>) -> Option<
       date: Some(time),
     });
   }
-  Some(format!("It's your momas turn yourself {} !", user))
-}
-
-#[command]
-pub async fn mom_change(params: CallBackParams) -> CallbackReturn {
-  Ok(mom_change_cmdless(&params.args[1], *params.message.timestamp).await)
+  Ok(Some(format!("It's your momas turn yourself {} !", user)))
 }
 
-pub async fn which_mom_cmdless() -> Option<String> {
+#[command(
+  name = "mom",
+  usage = "@BOT mom",
+  permission = User
+)]
+pub async fn which_mom(_: CallBackParams) -> CallbackReturn {
   let db_instance = database::INSTANCE.write().unwrap();
   let currentmom = db_instance.find_storage_type(StorageDataType::Mom);
   if let Some(mom) = currentmom {
-    Some(format!("It's currently {} mom's", mom.data))
+    Ok(Some(format!("It's currently {} mom's", mom.data)))
   } else {
-    Some(String::from("Nobody is in trouble for now..."))
+    Ok(Some(String::from("Nobody is in trouble for now...")))
   }
 }
 
-#[command]
-pub async fn which_mom(_: CallBackParams) -> CallbackReturn {
-  Ok(which_mom_cmdless().await)
-}
-
-#[command]
+#[command(
+  name = "cat",
+  usage = "@BOT cat",
+  permission = Guest
+)]
 pub async fn get_cat_pic(_: CallBackParams) -> CallbackReturn {
   let response =
     reqwest::blocking::get("https://api.thecatapi.com/v1/images/search?size=full").unwrap();
@@ -1,6 +1,6 @@
 use crate::core::{
+  arguments::FromArgument,
   commands::{CallBackParams, CallbackReturn},
-  parse::{discord_str_to_id, DiscordIds},
   permissions::member_channel_read,
 };
 use crate::database::INSTANCE;
@@ -57,51 +57,52 @@ pub async fn on_new_member_check(ctx: Context, member: &mut Member) {
   }
 }
 
-fn parse_create_argument(
-  argument: &str,
-  role: &mut Option<i64>,
-  channel: &mut Option<i64>,
-) -> Result<(), String> {
-  let param1 = discord_str_to_id(argument, None)?;
-  let result = match param1.1 {
-    DiscordIds::Role => param1,
-    DiscordIds::Channel => param1,
-    _ => return Err(String::from("Id provided should be a Channel or a Role")),
-  };
+enum InviteAction {
+  Role(RoleId),
+  Channel(ChannelId),
+}
 
-  match result.1 {
-    DiscordIds::Role => {
-      if role.is_some() {
-        return Err(String::from("Role was already specified"));
-      }
-      *role = Some(result.0 as i64);
-    }
-    DiscordIds::Channel => {
-      if channel.is_some() {
-        return Err(String::from("Channel was already specified"));
-      }
-      *channel = Some(result.0 as i64)
-    }
-    _ => unreachable!(),
+impl FromArgument for InviteAction {
+  const EXPECTED: &'static str = "expected a role or a channel: <@&role> or <#channel>";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    RoleId::from_argument(argument)
+      .map(InviteAction::Role)
+      .or_else(|_| ChannelId::from_argument(argument).map(InviteAction::Channel))
+      .map_err(|_| String::from(Self::EXPECTED))
   }
-  Ok(())
 }
 
-#[command]
+#[command(
+  name = "invite",
+  usage = "@BOT invite [<#invitecode>] <role AND OR channel>",
+  min_args = 2,
+  max_args = 3,
+  permission = User
+)]
 pub async fn create(params: CallBackParams) -> CallbackReturn {
+  let mut arguments = params.arguments();
+  let code = arguments.next_raw()?.replace("https://discord.gg/", "");
   let mut role = None;
   let mut channel = None;
-  if let Err(err) = parse_create_argument(&params.args[2], &mut role, &mut channel) {
-    return Ok(Some(err));
-  }
-  if params.args.len() == 4 {
-    if let Err(err) = parse_create_argument(&params.args[3], &mut role, &mut channel) {
-      return Ok(Some(err));
+  for action in arguments.many::<InviteAction>()? {
+    match action {
+      InviteAction::Role(role_id) => {
+        if role.is_some() {
+          return Ok(Some(String::from("Role was already specified")));
+        }
+        role = Some(role_id.0 as i64);
+      }
+      InviteAction::Channel(channel_id) => {
+        if channel.is_some() {
+          return Ok(Some(String::from("Channel was already specified")));
+        }
+        channel = Some(channel_id.0 as i64);
+      }
     }
   }
   {
     let mut db_instance = INSTANCE.write().unwrap();
-    let code = &params.args[1].replace("https://discord.gg/", "");
     if code.len() < 8 {
       return Ok(Some(format!("Invite code: {}, isn't valid", code)));
     }
@@ -20,6 +20,7 @@ pub mod invite_action;
 pub mod mecleanup;
 pub mod ordering;
 pub mod project_manager;
+pub mod recurrence;
 pub mod renaming;
 pub mod threadcontrol;
 
@@ -48,6 +49,8 @@ impl Features {
   pub fn run(&mut self, http: &Arc<http::Http>) {
     info!("Running features");
     let http_clone = http.clone();
-    tokio::spawn(async { events::check_events_loop(http_clone).await });
+    tokio::spawn(async { events::scheduler::run(http_clone).await });
+    let http_clone = http.clone();
+    tokio::spawn(async { archivage::scheduled::run(http_clone).await });
   }
 }
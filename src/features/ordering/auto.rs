@@ -0,0 +1,119 @@
+//! Ordering applied again when a channel is created, renamed or moved, for the rules set
+//! with `auto=yes`.
+//!
+//! The events are debounced so a burst of changes, like the creation of a project,
+//! reorders the category once.
+use super::{reorder, rules::OrderingTarget};
+use crate::database::INSTANCE;
+use serenity::{
+  model::{
+    channel::{ChannelType, GuildChannel},
+    id::GuildId,
+  },
+  prelude::*,
+};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use tokio::time;
+
+/// Time without event before the channels are reordered
+const DEBOUNCE_SECS: u64 = 10;
+
+lazy_static! {
+  static ref PENDING: Mutex<Debouncer<(GuildId, OrderingTarget)>> =
+    Mutex::new(Debouncer::default());
+}
+
+/// Latest event of each key, only the last one of a burst is applied
+#[derive(Debug)]
+struct Debouncer<K> {
+  events: HashMap<K, u64>,
+  count: u64,
+}
+
+impl<K> Default for Debouncer<K> {
+  fn default() -> Self {
+    Debouncer {
+      events: HashMap::new(),
+      count: 0,
+    }
+  }
+}
+
+impl<K: std::hash::Hash + Eq> Debouncer<K> {
+  /// Returns the id of the event
+  fn push(&mut self, key: K) -> u64 {
+    self.count += 1;
+    self.events.insert(key, self.count);
+    self.count
+  }
+
+  /// Whether the event is still the latest of its key, the key is then forgotten
+  fn take(&mut self, key: &K, event: u64) -> bool {
+    if self.events.get(key) != Some(&event) {
+      return false;
+    }
+    self.events.remove(key);
+    true
+  }
+}
+
+/// The categories are ordered together, the other channels within their category
+fn target_of(channel: &GuildChannel) -> OrderingTarget {
+  match (channel.kind, channel.parent_id) {
+    (ChannelType::Category, _) => OrderingTarget::Categories,
+    (_, Some(category)) => OrderingTarget::Category(category.0),
+    (_, None) => OrderingTarget::Category(0),
+  }
+}
+
+fn is_auto(guild_id: GuildId, target: OrderingTarget) -> bool {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .ordering_rule_search(guild_id.0, target.category_id())
+    .filter(|rule| rule.auto)
+    .is_some()
+}
+
+/// Called when the channel is created or its name or category changes, `old` is the
+/// channel before the change
+pub fn channel_changed(ctx: &Context, channel: &GuildChannel, old: Option<&GuildChannel>) {
+  let target = target_of(channel);
+  queue(ctx, channel.guild_id, target);
+  // The category it left is reordered too
+  if let Some(old_target) = old
+    .map(target_of)
+    .filter(|old_target| *old_target != target)
+  {
+    queue(ctx, channel.guild_id, old_target);
+  }
+}
+
+/// Reorder the target once its events stop, when its rule is set with auto
+fn queue(ctx: &Context, guild_id: GuildId, target: OrderingTarget) {
+  let key = (guild_id, target);
+  if !is_auto(key.0, key.1) {
+    return;
+  }
+  let event = PENDING.lock().unwrap().push(key);
+  let ctx = ctx.clone();
+  tokio::spawn(async move {
+    time::sleep(Duration::from_secs(DEBOUNCE_SECS)).await;
+    let latest = PENDING.lock().unwrap().take(&key, event);
+    if latest {
+      reorder(key.0, key.1, &ctx).await;
+    }
+  });
+}
+
+#[test]
+fn test_debouncer() {
+  let mut debouncer = Debouncer::default();
+  let first = debouncer.push("projects");
+  let other = debouncer.push("general");
+  let last = debouncer.push("projects");
+  assert!(!debouncer.take(&"projects", first));
+  assert!(debouncer.take(&"projects", last));
+  // An event is applied once
+  assert!(!debouncer.take(&"projects", last));
+  assert!(debouncer.take(&"general", other));
+}
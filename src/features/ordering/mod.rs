@@ -0,0 +1,182 @@
+use std::fmt::Write;
+
+use crate::{
+  core::{
+    commands::{CallBackParams, CallbackReturn},
+    parse::DiscordIds,
+    validation::{self, ValidationAction},
+  },
+  database::{OrderingRule, OrderingSort, INSTANCE},
+};
+use procedural_macros::command;
+use serenity::{
+  cache::Cache,
+  http::Http,
+  model::{
+    channel::{ChannelType, GuildChannel},
+    id::{ChannelId, GuildId},
+  },
+  prelude::*,
+};
+
+use super::archivage::{filter_guild_channel, last_activity};
+use rules::{sort_channels, OrderingTarget, SortKey};
+
+pub mod auto;
+pub mod rules;
+
+#[command(
+  name = "ordering",
+  usage = "@BOT ordering [<category> | categories | uncategorized]",
+  max_args = 1,
+  permission = Admin
+)]
+pub async fn ordering_channel_command(params: CallBackParams) -> CallbackReturn {
+  let target = params
+    .arguments()
+    .optional::<OrderingTarget>()?
+    .unwrap_or(OrderingTarget::Category(0));
+  let gid = params.message.guild_id.unwrap();
+  let (ordering, action) = match guild_chanels_ordering(gid, target, params.context).await {
+    Some(res) => res,
+    None => return Ok(Some(String::from("Channels are already ordered"))),
+  };
+  validation::validate_command(&ordering, params.message, params.context, action).await?;
+  Ok(None)
+}
+
+/// Keys of the channels with the deadline of their project
+fn sort_keys(channels: &[&GuildChannel]) -> Vec<SortKey> {
+  let db_instance = INSTANCE.read().unwrap();
+  channels
+    .iter()
+    .map(|channel| SortKey {
+      id: channel.id.0,
+      name: channel.name.clone(),
+      last_activity: last_activity(channel),
+      deadline: db_instance
+        .projects_search(channel.id.0 as i64, DiscordIds::Channel)
+        .and_then(|(_, project)| project.deadline_date),
+    })
+    .collect()
+}
+
+/// The channels of the target are sorted by its rule, by name without rule
+fn ordering_channels_type(
+  channels: &[GuildChannel],
+  chantype: ChannelType,
+  target: OrderingTarget,
+  rule: Option<&OrderingRule>,
+) -> (String, Vec<ChannelId>) {
+  let channels: Vec<&GuildChannel> = channels
+    .iter()
+    .filter(|chan| {
+      chan.kind == chantype
+        && match (target, chan.parent_id) {
+          (OrderingTarget::Categories, _) => true,
+          (OrderingTarget::Category(category), Some(chan)) => chan == category,
+          (OrderingTarget::Category(category), None) => category == 0,
+        }
+    })
+    .collect();
+  let mut keys = sort_keys(&channels);
+  match rule {
+    Some(rule) => sort_channels(&mut keys, rule.sort(), &rule.pinned),
+    None => sort_channels(&mut keys, OrderingSort::Name, &[]),
+  }
+  let mut display = String::new();
+  let mut ordered_channels: Vec<ChannelId> = Vec::new();
+  for (index, key) in keys.iter().enumerate() {
+    let channel = channels
+      .iter()
+      .find(|channel| channel.id.0 == key.id)
+      .expect("sorted channels are the same");
+    if channel.position != index as i64 {
+      writeln!(
+        display,
+        "[{}] {} => {}",
+        channel.name(),
+        channel.position,
+        index
+      )
+      .expect("unable to append in string");
+    }
+    ordered_channels.push(channel.id);
+  }
+  (display, ordered_channels)
+}
+
+/// The positions of all the channels are changed by a single request
+pub async fn ordering_channels_apply(gid: GuildId, new_orders: Vec<Vec<ChannelId>>, http: &Http) {
+  let positions = new_orders.into_iter().flat_map(|new_order| {
+    new_order
+      .into_iter()
+      .enumerate()
+      .map(|(index, channel_id)| (channel_id, index as u64))
+  });
+  if let Err(why) = gid.reorder_channels(http, positions).await {
+    // TODO: Should tell the user about it
+    error!("Unable to reorder the channels of {}:\n{}", gid, why);
+  }
+}
+
+/// Preview of the changes with the new order of each type of channel, None if the
+/// channels are already ordered
+fn guild_new_orders(
+  gid: GuildId,
+  target: OrderingTarget,
+  cache: &Cache,
+) -> Option<(String, Vec<Vec<ChannelId>>)> {
+  let rule = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .ordering_rule_search(gid.0, target.category_id())
+      .cloned()
+  };
+  let channels = match cache.guild(gid) {
+    Some(guild) => filter_guild_channel(guild.channels),
+    None => {
+      error!("Guild {} not found to order its channels", gid);
+      return None;
+    }
+  };
+  let kinds: &[(&str, ChannelType)] = match target {
+    OrderingTarget::Categories => &[("Categories", ChannelType::Category)],
+    OrderingTarget::Category(_) => &[("Texts", ChannelType::Text), ("Voices", ChannelType::Voice)],
+  };
+  let mut preview_reply = String::from("Order prevision:");
+  let mut new_orders: Vec<Vec<ChannelId>> = Vec::new();
+  for (name, kind) in kinds {
+    let (display, new_order) = ordering_channels_type(&channels, *kind, target, rule.as_ref());
+    if !display.is_empty() {
+      write!(preview_reply, "\n{}:\n{}", name, display).expect("unable to append in string");
+      new_orders.push(new_order);
+    }
+  }
+  if new_orders.is_empty() {
+    return None;
+  }
+  Some((preview_reply, new_orders))
+}
+
+/// Apply the rule of the target without validation
+pub async fn reorder(gid: GuildId, target: OrderingTarget, context: &Context) {
+  if let Some((_, new_orders)) = guild_new_orders(gid, target, &context.cache) {
+    ordering_channels_apply(gid, new_orders, &context.http).await;
+  }
+}
+
+pub async fn guild_chanels_ordering(
+  gid: GuildId,
+  target: OrderingTarget,
+  context: &Context,
+) -> Option<(String, ValidationAction)> {
+  let (preview_reply, new_orders) = guild_new_orders(gid, target, &context.cache)?;
+  let action = ValidationAction::Reorder {
+    new_orders: new_orders
+      .into_iter()
+      .map(|new_order| new_order.into_iter().map(|channel| channel.0).collect())
+      .collect(),
+  };
+  Some((preview_reply, action))
+}
@@ -0,0 +1,291 @@
+//! Rules ordering the channels of a category, set by `ordering-rule`.
+//!
+//! The pinned channels stay at the top in their order, the others are sorted by name,
+//! natural name, last activity or deadline of their project. Without rule a category is
+//! sorted by name.
+use crate::{
+  core::{
+    arguments::{ArgumentError, FromArgument},
+    commands::{CallBackParams, CallbackReturn},
+  },
+  database::{NewOrderingRule, OrderingRule, OrderingSort, INSTANCE},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use procedural_macros::command;
+use serenity::{model::id::ChannelId, prelude::Mentionable};
+use std::{cmp::Ordering, fmt::Write};
+
+/// Channels ordered by a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderingTarget {
+  Categories,
+  /// Channels of the category, 0 for the channels without category
+  Category(u64),
+}
+
+impl OrderingTarget {
+  /// Stored as the category of the rule
+  pub fn category_id(self) -> Option<u64> {
+    match self {
+      OrderingTarget::Categories => None,
+      OrderingTarget::Category(category) => Some(category),
+    }
+  }
+
+  fn display(self) -> String {
+    match self {
+      OrderingTarget::Categories => String::from("categories"),
+      OrderingTarget::Category(0) => String::from("uncategorized"),
+      OrderingTarget::Category(category) => ChannelId(category).mention().to_string(),
+    }
+  }
+}
+
+impl FromArgument for OrderingTarget {
+  const EXPECTED: &'static str = "expected <#category>, categories or uncategorized";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    match argument {
+      "categories" => Ok(OrderingTarget::Categories),
+      "uncategorized" => Ok(OrderingTarget::Category(0)),
+      _ => ChannelId::from_argument(argument)
+        .map(|category| OrderingTarget::Category(category.0))
+        .map_err(|_| String::from(Self::EXPECTED)),
+    }
+  }
+}
+
+impl FromArgument for OrderingSort {
+  const EXPECTED: &'static str = "expected name, natural, activity or deadline";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    argument.parse().map_err(|_| String::from(Self::EXPECTED))
+  }
+}
+
+/// What a channel is sorted on
+#[derive(Debug, Clone)]
+pub struct SortKey {
+  pub id: u64,
+  pub name: String,
+  pub last_activity: DateTime<Utc>,
+  /// Deadline of the project of the channel
+  pub deadline: Option<NaiveDate>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum NaturalChunk {
+  /// Compared by number of digits then by digits, so any length is compared by value
+  Number(usize, String),
+  Text(String),
+}
+
+fn natural_key(name: &str) -> Vec<NaturalChunk> {
+  let mut chunks = Vec::new();
+  let mut chars = name.chars().peekable();
+  while let Some(&first) = chars.peek() {
+    let is_digit = first.is_ascii_digit();
+    let mut chunk = String::new();
+    while let Some(&c) = chars.peek() {
+      if c.is_ascii_digit() != is_digit {
+        break;
+      }
+      chunk.push(c);
+      chars.next();
+    }
+    if is_digit {
+      let digits = chunk.trim_start_matches('0').to_string();
+      chunks.push(NaturalChunk::Number(digits.len(), digits));
+    } else {
+      chunks.push(NaturalChunk::Text(chunk.to_lowercase()));
+    }
+  }
+  chunks
+}
+
+/// `project-2` comes before `project-10`
+pub fn natural_cmp(name: &str, other: &str) -> Ordering {
+  natural_key(name).cmp(&natural_key(other))
+}
+
+/// Sort the channels, the ties are sorted by name
+pub fn sort_channels(channels: &mut [SortKey], sort: OrderingSort, pinned: &[i64]) {
+  let pinned_index = |key: &SortKey| {
+    pinned
+      .iter()
+      .position(|id| *id == key.id as i64)
+      .unwrap_or(usize::MAX)
+  };
+  channels.sort_by(|key, other| {
+    let by_rule = match sort {
+      OrderingSort::Name => Ordering::Equal,
+      OrderingSort::Natural => natural_cmp(&key.name, &other.name),
+      OrderingSort::Activity => other.last_activity.cmp(&key.last_activity),
+      OrderingSort::Deadline => {
+        (key.deadline.is_none(), key.deadline).cmp(&(other.deadline.is_none(), other.deadline))
+      }
+    };
+    pinned_index(key)
+      .cmp(&pinned_index(other))
+      .then(by_rule)
+      .then_with(|| key.name.cmp(&other.name))
+  });
+}
+
+fn describe(rule: &OrderingRule) -> String {
+  let target = match rule.category_id {
+    Some(category) => OrderingTarget::Category(category as u64),
+    None => OrderingTarget::Categories,
+  };
+  let mut display = format!("**{}**: {}", target.display(), rule.sort());
+  if !rule.pinned.is_empty() {
+    let pinned: Vec<String> = rule
+      .pinned
+      .iter()
+      .map(|id| ChannelId(*id as u64).mention().to_string())
+      .collect();
+    write!(display, ", pinned: {}", pinned.join(", ")).expect("unable to append in string");
+  }
+  if rule.auto {
+    display.push_str(", applied automatically");
+  }
+  display
+}
+
+#[command(
+  name = "ordering-rule",
+  usage = "@BOT ordering-rule <list | set <<#category> | categories | uncategorized> <name | natural | activity | deadline> [pin=<#channel1>,<#channel2>] [auto=<yes|no>] | reset <<#category> | categories | uncategorized>>",
+  min_args = 1,
+  max_args = 5,
+  permission = Admin
+)]
+pub async fn ordering_rule(params: CallBackParams) -> CallbackReturn {
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a guild channel",
+      )))
+    }
+  };
+  let mut arguments = params.arguments();
+  match arguments.next_raw()? {
+    "list" => {
+      let db_instance = INSTANCE.read().unwrap();
+      let mut display = String::new();
+      for rule in db_instance
+        .ordering_rules
+        .iter()
+        .filter(|rule| rule.guild_id == guild_id.0 as i64)
+      {
+        writeln!(display, "{}", describe(rule)).expect("unable to append in string");
+      }
+      if display.is_empty() {
+        display = String::from("No ordering rule, the channels are sorted by name");
+      }
+      Ok(Some(display))
+    }
+    "set" => {
+      let target: OrderingTarget = arguments.next()?;
+      let sort: OrderingSort = arguments.next()?;
+      if target == OrderingTarget::Categories
+        && !matches!(sort, OrderingSort::Name | OrderingSort::Natural)
+      {
+        return Ok(Some(String::from(
+          "Categories can only be sorted by name or natural",
+        )));
+      }
+      let values = arguments.key_values(&["pin", "auto"])?;
+      let pinned = match values.get("pin") {
+        Some(pinned) => match Vec::<ChannelId>::from_argument(pinned) {
+          Ok(pinned) => pinned,
+          Err(why) => return Ok(Some(format!("Invalid pin: {}", why))),
+        },
+        None => Vec::new(),
+      };
+      let auto = match values.get("auto") {
+        Some(&"yes") => true,
+        Some(&"no") | None => false,
+        Some(_) => return Ok(Some(String::from("Invalid auto: expected yes or no"))),
+      };
+      let mut db_instance = INSTANCE.write().unwrap();
+      db_instance.ordering_rule_set(NewOrderingRule {
+        guild_id: guild_id.0 as i64,
+        category_id: target.category_id().map(|id| id as i64),
+        sort: &sort.to_string(),
+        pinned: pinned.into_iter().map(|channel| channel.0 as i64).collect(),
+        auto,
+      });
+      Ok(Some(String::from(":ok:")))
+    }
+    "reset" => {
+      let target: OrderingTarget = arguments.next()?;
+      let mut db_instance = INSTANCE.write().unwrap();
+      if db_instance.ordering_rule_delete(guild_id.0, target.category_id()) {
+        Ok(Some(String::from(":ok:")))
+      } else {
+        Ok(Some(format!("There is no rule for {}", target.display())))
+      }
+    }
+    action => Err(
+      ArgumentError {
+        position: 1,
+        value: Some(action.to_string()),
+        reason: String::from("expected list, set or reset"),
+      }
+      .into(),
+    ),
+  }
+}
+
+#[test]
+fn test_sort_channels() {
+  use chrono::TimeZone;
+
+  let mut names = vec![
+    "project-10",
+    "Project-2",
+    "project-1b",
+    "project-02a",
+    "alpha",
+  ];
+  names.sort_by(|name, other| natural_cmp(name, other));
+  assert_eq!(
+    names,
+    vec![
+      "alpha",
+      "project-1b",
+      "Project-2",
+      "project-02a",
+      "project-10"
+    ]
+  );
+
+  let key = |id: u64, name: &str, day: u32, deadline: Option<u32>| SortKey {
+    id,
+    name: String::from(name),
+    last_activity: Utc.ymd(2022, 10, day).and_hms(12, 0, 0),
+    deadline: deadline.map(|day| NaiveDate::from_ymd(2022, 11, day)),
+  };
+  let mut channels = vec![
+    key(1, "general", 1, None),
+    key(2, "project-b", 20, Some(30)),
+    key(3, "project-a", 10, Some(5)),
+    key(4, "project-c", 30, None),
+  ];
+  let ids = |channels: &[SortKey]| channels.iter().map(|key| key.id).collect::<Vec<u64>>();
+
+  sort_channels(&mut channels, OrderingSort::Activity, &[1]);
+  assert_eq!(ids(&channels), vec![1, 4, 2, 3]);
+  // Channels without deadline come last, sorted by name
+  sort_channels(&mut channels, OrderingSort::Deadline, &[]);
+  assert_eq!(ids(&channels), vec![3, 2, 1, 4]);
+  sort_channels(&mut channels, OrderingSort::Name, &[4, 2]);
+  assert_eq!(ids(&channels), vec![4, 2, 1, 3]);
+
+  assert_eq!(
+    OrderingTarget::from_argument("categories"),
+    Ok(OrderingTarget::Categories)
+  );
+  assert!(OrderingTarget::from_argument("general").is_err());
+}
@@ -0,0 +1,135 @@
+//! Reminders sent in the channel of a project before and after its deadline.
+//!
+//! They are events of the project sent by the events scheduler to the lead,
+//! `PROJECT_DEADLINE_REMINDERS` lists the days before the deadline they are sent,
+//! a negative day being after the deadline. It defaults to `7,1,-1`.
+use crate::{
+  core::{
+    arguments::FromArgument,
+    parse::{self, DiscordIds},
+    timezone::user_timezone,
+  },
+  database::{Delivery, NewEvent, Project, ProjectState, INSTANCE},
+  features::events::{dateparse::resolve_local, scheduler},
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serenity::{
+  model::id::{ChannelId, UserId},
+  prelude::Mentionable,
+};
+use std::env;
+
+lazy_static! {
+  static ref DEADLINE_REMINDERS: Vec<i64> = match env::var("PROJECT_DEADLINE_REMINDERS") {
+    Ok(days) => parse_reminder_days(&days).unwrap_or_else(|| {
+      error!("Invalid PROJECT_DEADLINE_REMINDERS {}", days);
+      DEFAULT_REMINDERS.to_vec()
+    }),
+    Err(_) => DEFAULT_REMINDERS.to_vec(),
+  };
+}
+
+const DEFAULT_REMINDERS: [i64; 3] = [7, 1, -1];
+/// Time of the reminders in the timezone of the lead
+const REMINDER_HOUR: u32 = 9;
+
+fn parse_reminder_days(days: &str) -> Option<Vec<i64>> {
+  days.split(',').map(|day| day.trim().parse().ok()).collect()
+}
+
+/// The deadline given to a project, "N/A" is no deadline
+pub fn parse_deadline(deadline: Option<&str>) -> Result<Option<NaiveDate>, String> {
+  let deadline = match deadline {
+    Some(deadline) if deadline != "N/A" => deadline,
+    _ => return Ok(None),
+  };
+  let date = NaiveDate::from_argument(deadline)
+    .map_err(|reason| format!("Invalid deadline `{}`: {}", deadline, reason))?;
+  if date < Utc::now().naive_utc().date() {
+    return Err(format!("The deadline `{}` is already past", deadline));
+  }
+  Ok(Some(date))
+}
+
+fn reminder_content(project_chan: ChannelId, deadline: NaiveDate, days: i64) -> String {
+  let when = match days {
+    0 => String::from("is today"),
+    1 => String::from("is tomorrow"),
+    days if days > 0 => format!("is in {} days", days),
+    _ => String::from("is overdue"),
+  };
+  format!(
+    "The deadline of {} {} ({})",
+    project_chan.mention(),
+    when,
+    deadline.format("%d/%m/%Y")
+  )
+}
+
+/// The lead is pinged when they were mentioned, `author` otherwise.
+/// The reminders of a closed project are removed.
+pub fn schedule_deadline_reminders(project: &Project, author: UserId) {
+  let lead = match parse::discord_str_to_id(&project.lead, Some(DiscordIds::User)) {
+    Ok((lead, _)) => UserId(lead),
+    Err(_) => author,
+  };
+  let timezone = user_timezone(lead);
+  let now = Utc::now().naive_utc();
+  let project_chan = ChannelId(project.channel_id as u64);
+  let reminders: Vec<(NaiveDateTime, String)> = match project.deadline_date {
+    Some(deadline) if project.state() != ProjectState::Closed => DEADLINE_REMINDERS
+      .iter()
+      .filter_map(|days| {
+        let day = deadline - Duration::days(*days);
+        let trigger_date = resolve_local(
+          timezone,
+          day.and_time(NaiveTime::from_hms(REMINDER_HOUR, 0, 0)),
+        )?
+        .naive_utc();
+        if trigger_date <= now {
+          return None;
+        }
+        Some((
+          trigger_date,
+          reminder_content(project_chan, deadline, *days),
+        ))
+      })
+      .collect(),
+    _ => Vec::new(),
+  };
+
+  let mut db_instance = INSTANCE.write().unwrap();
+  db_instance.event_delete_project(project.id);
+  for (trigger_date, content) in reminders {
+    db_instance.event_add(NewEvent {
+      author: lead.0 as i64,
+      channel: project.channel_id,
+      content: &content,
+      trigger_date,
+      recurrence: None,
+      delivery: &Delivery::Channel.to_string(),
+      project_id: Some(project.id),
+    });
+  }
+  scheduler::reschedule();
+}
+
+#[test]
+fn test_deadline_reminders() {
+  assert_eq!(parse_reminder_days("7, 1,-1"), Some(vec![7, 1, -1]));
+  assert_eq!(parse_reminder_days("7,soon"), None);
+
+  assert_eq!(parse_deadline(Some("N/A")), Ok(None));
+  assert!(parse_deadline(Some("someday")).is_err());
+  assert!(parse_deadline(Some("01/01/2020")).is_err());
+
+  let deadline = NaiveDate::from_ymd(2022, 10, 30);
+  assert_eq!(
+    reminder_content(ChannelId(42), deadline, 7),
+    "The deadline of <#42> is in 7 days (30/10/2022)"
+  );
+  assert_eq!(
+    reminder_content(ChannelId(42), deadline, -1),
+    "The deadline of <#42> is overdue (30/10/2022)"
+  );
+}
@@ -0,0 +1,268 @@
+//! Dossier of a project: its card and the messages of its channel with their edits.
+//!
+//! It's built from the database as JSON and Markdown, sent as files by `project-export`
+//! and served by the api on `/auth/project/<channel>/export`.
+use super::state_label;
+use crate::{
+  core::{
+    commands::{CallBackParams, CallbackReturn},
+    parse::DiscordIds,
+    timezone::user_timezone,
+  },
+  database::{Project, ProjectState, INSTANCE},
+};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use procedural_macros::command;
+use serenity::{cache::Cache, model::channel::AttachmentType, prelude::Mentionable};
+use std::{borrow::Cow, fmt::Write, time::SystemTime};
+
+#[derive(Debug, Serialize)]
+pub struct ExportedEdit {
+  pub date: Option<DateTime<Utc>>,
+  pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedMessage {
+  pub id: i64,
+  pub author: i64,
+  /// Name of the author when they are known by the bot
+  pub author_name: Option<String>,
+  pub date: Option<DateTime<Utc>>,
+  pub content: String,
+  pub attachments: Vec<String>,
+  pub edits: Vec<ExportedEdit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectExport {
+  pub channel_id: i64,
+  pub created_at: DateTime<Utc>,
+  pub state: String,
+  pub codex: String,
+  pub client: String,
+  pub lead: String,
+  pub deadline: String,
+  pub description: String,
+  pub contexte: String,
+  pub messages: Vec<ExportedMessage>,
+}
+
+fn utc(date: Option<SystemTime>) -> Option<DateTime<Utc>> {
+  date.map(DateTime::<Utc>::from)
+}
+
+/// None if the channel isn't a project
+pub fn build_export(channel_id: u64, cache: &Cache) -> Option<ProjectExport> {
+  let db_instance = INSTANCE.read().unwrap();
+  let project: &Project = db_instance
+    .projects_search(channel_id as i64, DiscordIds::Channel)?
+    .1;
+  let mut messages: Vec<ExportedMessage> = db_instance
+    .messages
+    .iter()
+    .filter(|message| message.channel == project.channel_id)
+    .map(|message| ExportedMessage {
+      id: message.id,
+      author: message.author,
+      author_name: cache.user(message.author as u64).map(|user| user.tag()),
+      date: utc(message.date),
+      content: message.content.clone(),
+      attachments: message.attachments.clone(),
+      edits: db_instance
+        .messages_edits
+        .iter()
+        .filter(|edit| edit.parrent_message_id == message.id)
+        .map(|edit| ExportedEdit {
+          date: utc(edit.date),
+          content: edit.content.clone(),
+        })
+        .collect(),
+    })
+    .collect();
+  messages.sort_by_key(|message| message.date);
+  Some(ProjectExport {
+    channel_id: project.channel_id,
+    created_at: project.created_at.into(),
+    state: project.state.clone(),
+    codex: project.codex.clone(),
+    client: project.client.clone(),
+    lead: project.lead.clone(),
+    deadline: project.deadline.clone(),
+    description: project.description.clone(),
+    contexte: project.contexte.clone(),
+    messages,
+  })
+}
+
+/// Dates are displayed in `timezone`
+pub fn to_markdown(export: &ProjectExport, timezone: Tz) -> String {
+  let format_date = |date: Option<DateTime<Utc>>| match date {
+    Some(date) => date
+      .with_timezone(&timezone)
+      .format("%d/%m/%Y %H:%M")
+      .to_string(),
+    None => String::from("N/A"),
+  };
+  let mut markdown = String::new();
+  writeln!(
+    markdown,
+    "# {} {}
+
+**Date de création** : {}
+**Statut** : {}
+**Client** : {}
+**Codex** : {}
+**Lead projet** : {}
+**Deadline (si applicable)** : {}
+**Brief projet** : {}
+**Contexte projet** : {}
+
+## Messages ({})",
+    export.codex,
+    export.client,
+    export
+      .created_at
+      .with_timezone(&timezone)
+      .format("%d/%m/%Y"),
+    state_label(export.state.parse().unwrap_or(ProjectState::Active)),
+    export.client,
+    export.codex,
+    export.lead,
+    export.deadline,
+    export.description,
+    export.contexte,
+    export.messages.len()
+  )
+  .expect("unable to append in string");
+  for message in export.messages.iter() {
+    let author = message
+      .author_name
+      .clone()
+      .unwrap_or_else(|| message.author.to_string());
+    write!(
+      markdown,
+      "\n### {} - {}\n\n{}\n",
+      author,
+      format_date(message.date),
+      message.content
+    )
+    .expect("unable to append in string");
+    for attachment in message.attachments.iter() {
+      writeln!(markdown, "- {}", attachment).expect("unable to append in string");
+    }
+    for edit in message.edits.iter() {
+      writeln!(
+        markdown,
+        "\n> Edited {}: {}",
+        format_date(edit.date),
+        edit.content.replace('\n', "\n> ")
+      )
+      .expect("unable to append in string");
+    }
+  }
+  markdown
+}
+
+/// Name of the exported files, from the codex or the channel of the project without one
+fn export_name(export: &ProjectExport) -> String {
+  let mut name = String::new();
+  for c in export.codex.to_lowercase().chars() {
+    if c.is_ascii_lowercase() || c.is_ascii_digit() {
+      name.push(c);
+    } else if !name.is_empty() && !name.ends_with('-') {
+      name.push('-');
+    }
+  }
+  let name = name.trim_end_matches('-');
+  // "N/A" is the codex of the projects created without one
+  if name.is_empty() || export.codex == "N/A" {
+    return format!("project-{}", export.channel_id);
+  }
+  name.to_string()
+}
+
+#[command(
+  name = "project-export",
+  usage = "@BOT project-export",
+  permission = User
+)]
+pub async fn export(params: CallBackParams) -> CallbackReturn {
+  let channel_id = params.message.channel_id;
+  let export = match build_export(channel_id.0, &params.context.cache) {
+    Some(export) => export,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a project channel",
+      )))
+    }
+  };
+  let markdown = to_markdown(&export, user_timezone(params.message.author.id));
+  let json = serde_json::to_string_pretty(&export)?;
+  let name = export_name(&export);
+  channel_id
+    .send_message(&params.context.http, |message| {
+      message
+        .content(format!(
+          "Export of {} requested by {}",
+          channel_id.mention(),
+          params.message.author.mention()
+        ))
+        .add_files(vec![
+          AttachmentType::Bytes {
+            data: Cow::from(markdown.into_bytes()),
+            filename: format!("{}.md", name),
+          },
+          AttachmentType::Bytes {
+            data: Cow::from(json.into_bytes()),
+            filename: format!("{}.json", name),
+          },
+        ])
+    })
+    .await?;
+  Ok(None)
+}
+
+#[test]
+fn test_to_markdown() {
+  use chrono::TimeZone;
+  use chrono_tz::Europe::Paris;
+
+  let export = ProjectExport {
+    channel_id: 42,
+    created_at: Utc.ymd(2022, 10, 30).and_hms(17, 0, 0),
+    state: String::from("closed"),
+    codex: String::from("#P042"),
+    client: String::from("ACME"),
+    lead: String::from("<@1>"),
+    deadline: String::from("N/A"),
+    description: String::from("N/A"),
+    contexte: String::from("N/A"),
+    messages: vec![ExportedMessage {
+      id: 1,
+      author: 7,
+      author_name: None,
+      date: Some(Utc.ymd(2022, 10, 31).and_hms(9, 30, 0)),
+      content: String::from("First draft"),
+      attachments: vec![String::from("https://cdn.example/draft.png")],
+      edits: vec![ExportedEdit {
+        date: None,
+        content: String::from("First draft v2"),
+      }],
+    }],
+  };
+  let markdown = to_markdown(&export, Paris);
+  assert!(markdown.starts_with("# #P042 ACME"));
+  assert!(markdown.contains("**Statut** : Clôturé"));
+  assert!(markdown.contains("### 7 - 31/10/2022 10:30\n\nFirst draft\n"));
+  assert!(markdown.contains("- https://cdn.example/draft.png"));
+  assert!(markdown.contains("> Edited N/A: First draft v2"));
+
+  assert_eq!(export_name(&export), "p042");
+  let mut export = export;
+  export.codex = String::from("#P 12/b");
+  assert_eq!(export_name(&export), "p-12-b");
+  export.codex = String::from("N/A");
+  assert_eq!(export_name(&export), "project-42");
+}
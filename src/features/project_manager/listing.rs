@@ -0,0 +1,296 @@
+//! List the projects of the database as a paginated embed.
+//!
+//! The pages are changed with buttons, their custom id holds the page and the filters:
+//! `projects:<page>:<arg1>|<arg2>...`
+use crate::{
+  core::{
+    arguments::{Arguments, FromArgument},
+    commands::{CallBackParams, CallbackReturn},
+    config::{self, ConfigKey},
+  },
+  database::{Project, ProjectState, INSTANCE},
+};
+use log::error;
+use procedural_macros::command;
+use serenity::{
+  builder::{CreateComponents, CreateEmbed},
+  cache::Cache,
+  model::{
+    application::{
+      component::ButtonStyle,
+      interaction::{message_component::MessageComponentInteraction, InteractionResponseType},
+    },
+    channel::PermissionOverwriteType,
+    id::{ChannelId, GuildId, RoleId},
+  },
+  prelude::*,
+};
+
+pub const PROJECTS_ID: &str = "projects";
+const PROJECTS_PER_PAGE: usize = 10;
+/// Discord limits of a custom id and of an embed field
+const CUSTOM_ID_MAX_LEN: usize = 100;
+const FIELD_NAME_MAX_LEN: usize = 256;
+const FIELD_VALUE_MAX_LEN: usize = 1024;
+
+#[derive(Debug, Default, PartialEq)]
+struct ProjectFilter {
+  client: Option<String>,
+  lead: Option<String>,
+  archived: bool,
+}
+
+enum FilterOption {
+  Client(String),
+  Lead(String),
+  Archived,
+}
+
+impl FromArgument for FilterOption {
+  const EXPECTED: &'static str = "expected client=<client>, lead=<lead> or archived";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    // The filters are joined by `|` in the custom id of the page buttons
+    if argument.contains('|') {
+      return Err(String::from("a filter can't contain `|`"));
+    }
+    match argument.split_once('=') {
+      Some(("client", client)) => Ok(FilterOption::Client(client.to_lowercase())),
+      Some(("lead", lead)) => Ok(FilterOption::Lead(lead.to_lowercase())),
+      None if argument == "archived" => Ok(FilterOption::Archived),
+      _ => Err(String::from(Self::EXPECTED)),
+    }
+  }
+}
+
+impl ProjectFilter {
+  fn new(options: Vec<FilterOption>) -> Self {
+    let mut filter = ProjectFilter::default();
+    for option in options {
+      match option {
+        FilterOption::Client(client) => filter.client = Some(client),
+        FilterOption::Lead(lead) => filter.lead = Some(lead),
+        FilterOption::Archived => filter.archived = true,
+      }
+    }
+    filter
+  }
+
+  /// Client and lead match when they contain the filter, ignoring the case
+  fn matches(&self, project: &Project, archived: bool) -> bool {
+    let contains = |field: &str, filter: &Option<String>| match filter {
+      Some(filter) => field.to_lowercase().contains(filter),
+      None => true,
+    };
+    archived == self.archived
+      && contains(&project.client, &self.client)
+      && contains(&project.lead, &self.lead)
+  }
+}
+
+/// Projects of the guild matching the filter with their member count, sorted by codex
+fn find_projects(
+  filter: &ProjectFilter,
+  guild_id: GuildId,
+  cache: &Cache,
+) -> Vec<(Project, usize)> {
+  let archive_category = config::get(guild_id, ConfigKey::ArchiveCategory).map(ChannelId);
+  let db_instance = INSTANCE.read().unwrap();
+  let mut projects: Vec<(Project, usize)> = db_instance
+    .projects
+    .iter()
+    .filter_map(|project| {
+      let channel = cache.guild_channel(project.channel_id as u64)?;
+      if channel.guild_id != guild_id {
+        return None;
+      }
+      let archived = project.state() == ProjectState::Closed
+        || (archive_category.is_some() && channel.parent_id == archive_category);
+      if !filter.matches(project, archived) {
+        return None;
+      }
+      let members = match project.role_id {
+        Some(role_id) => cache
+          .guild(channel.guild_id)
+          .map(|guild| {
+            guild
+              .members
+              .values()
+              .filter(|member| member.roles.contains(&RoleId(role_id as u64)))
+              .count()
+          })
+          .unwrap_or(0),
+        None => channel
+          .permission_overwrites
+          .iter()
+          .filter(|overwrite| matches!(overwrite.kind, PermissionOverwriteType::Member(_)))
+          .count(),
+      };
+      Some((project.clone(), members))
+    })
+    .collect();
+  projects.sort_by(|(a, _), (b, _)| a.codex.cmp(&b.codex));
+  projects
+}
+
+fn pages_count(projects_count: usize) -> usize {
+  (projects_count.max(1) - 1) / PROJECTS_PER_PAGE + 1
+}
+
+fn projects_embed(projects: &[(Project, usize)], page: usize) -> CreateEmbed {
+  let mut embed = CreateEmbed::default();
+  embed.title(format!(
+    "Projects ({}) - page {}/{}",
+    projects.len(),
+    page + 1,
+    pages_count(projects.len())
+  ));
+  if projects.is_empty() {
+    embed.description("No project found");
+  }
+  for (project, members) in projects
+    .iter()
+    .skip(page * PROJECTS_PER_PAGE)
+    .take(PROJECTS_PER_PAGE)
+  {
+    let name = format!("{} {}", project.codex, project.client);
+    let value = format!(
+      "{}\nLead: {} | Deadline: {} | {} members | {}",
+      ChannelId(project.channel_id as u64).mention(),
+      project.lead,
+      project.deadline,
+      members,
+      project.state
+    );
+    embed.field(
+      name.chars().take(FIELD_NAME_MAX_LEN).collect::<String>(),
+      value.chars().take(FIELD_VALUE_MAX_LEN).collect::<String>(),
+      false,
+    );
+  }
+  embed
+}
+
+/// Previous and next buttons, `query` are the arguments of the command
+fn page_buttons(query: &str, page: usize, pages: usize) -> CreateComponents {
+  let mut components = CreateComponents::default();
+  components.create_action_row(|row| {
+    for (label, target, disabled) in [
+      ("Previous", page.saturating_sub(1), page == 0),
+      ("Next", page + 1, page + 1 >= pages),
+    ] {
+      row.create_button(|button| {
+        button
+          .custom_id(format!("{}:{}:{}", PROJECTS_ID, target, query))
+          .label(label)
+          .style(ButtonStyle::Secondary)
+          .disabled(disabled)
+      });
+    }
+    row
+  });
+  components
+}
+
+#[command(
+  name = "projects",
+  usage = "@BOT projects [client=<client>] [lead=<lead>] [archived]",
+  max_args = 3,
+  permission = User
+)]
+pub async fn projects(params: CallBackParams) -> CallbackReturn {
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a guild channel",
+      )))
+    }
+  };
+  let filter = ProjectFilter::new(params.arguments().many()?);
+  let query = params.arguments().rest().join("|");
+  if format!("{}:{}:{}", PROJECTS_ID, u16::MAX, query).len() > CUSTOM_ID_MAX_LEN {
+    return Ok(Some(String::from("The filters are too long")));
+  }
+  let projects = find_projects(&filter, guild_id, &params.context.cache);
+  let embed = projects_embed(&projects, 0);
+  let buttons = page_buttons(&query, 0, pages_count(projects.len()));
+  params
+    .message
+    .channel_id
+    .send_message(&params.context.http, |message| {
+      message.set_embed(embed).set_components(buttons)
+    })
+    .await?;
+  Ok(None)
+}
+
+/// Show the page of the button that was clicked
+pub async fn change_page(ctx: &Context, component: &MessageComponentInteraction) {
+  let guild_id = match component.guild_id {
+    Some(guild_id) => guild_id,
+    None => return error!("Projects button outside of a guild"),
+  };
+  let mut custom_id = component.data.custom_id.splitn(3, ':').skip(1);
+  let (page, query) = match (
+    custom_id.next().and_then(|page| page.parse::<usize>().ok()),
+    custom_id.next(),
+  ) {
+    (Some(page), Some(query)) => (page, query),
+    _ => return error!("Invalid projects button {}", component.data.custom_id),
+  };
+  // The command name comes first in the arguments
+  let args: Vec<String> = std::iter::once(PROJECTS_ID)
+    .chain(query.split('|').filter(|arg| !arg.is_empty()))
+    .map(String::from)
+    .collect();
+  let filter = match Arguments::new(&args).many() {
+    Ok(options) => ProjectFilter::new(options),
+    Err(why) => {
+      return error!(
+        "Invalid projects button {}: {}",
+        component.data.custom_id, why
+      )
+    }
+  };
+  let projects = find_projects(&filter, guild_id, &ctx.cache);
+  let pages = pages_count(projects.len());
+  let page = page.min(pages - 1);
+  let embed = projects_embed(&projects, page);
+  let buttons = page_buttons(query, page, pages);
+  let result = component
+    .create_interaction_response(&ctx.http, |res| {
+      res
+        .kind(InteractionResponseType::UpdateMessage)
+        .interaction_response_data(|data| data.set_embed(embed).set_components(buttons))
+    })
+    .await;
+  if let Err(why) = result {
+    error!("Unable to change the page of projects: {}", why);
+  }
+}
+
+#[test]
+fn test_project_filter() {
+  let parse = |args: &[&str]| {
+    let args: Vec<String> = std::iter::once(PROJECTS_ID)
+      .chain(args.iter().copied())
+      .map(String::from)
+      .collect();
+    Arguments::new(&args).many().map(ProjectFilter::new)
+  };
+  assert_eq!(
+    parse(&["client=ACME", "archived"]).unwrap(),
+    ProjectFilter {
+      client: Some(String::from("acme")),
+      lead: None,
+      archived: true,
+    }
+  );
+  assert_eq!(parse(&["codex=#P001"]).unwrap_err().position, 1);
+  assert_eq!(parse(&["archived", "lead=a|b"]).unwrap_err().position, 2);
+
+  assert_eq!(pages_count(0), 1);
+  assert_eq!(pages_count(10), 1);
+  assert_eq!(pages_count(11), 2);
+}
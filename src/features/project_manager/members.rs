@@ -0,0 +1,337 @@
+//! Roster of the members of each project, kept along their access to its channels.
+//!
+//! The access is given through the role of the project when it has one, with a
+//! permission overwrite on its channel otherwise.
+use crate::{
+  core::{
+    arguments::FromArgument,
+    commands::{CallBackParams, CallbackReturn},
+    parse::DiscordIds,
+    permissions::{member_channel_read, ReadState},
+    timezone::format_date,
+  },
+  database::{Project, INSTANCE},
+};
+use procedural_macros::command;
+use serenity::{
+  cache::Cache,
+  http::Http,
+  model::{
+    channel::{GuildChannel, PermissionOverwriteType},
+    id::{ChannelId, GuildId, RoleId, UserId},
+    Permissions,
+  },
+  prelude::Mentionable,
+  Error as SerenityError,
+};
+use std::{collections::HashSet, fmt::Write};
+
+/// Give or remove the access of the user to the channel, the roster is updated when the
+/// channel is a project
+pub async fn set_access(
+  http: &Http,
+  guild_id: GuildId,
+  channel_id: ChannelId,
+  user_id: UserId,
+  state: ReadState,
+) -> Result<(), SerenityError> {
+  let project = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .projects_search(channel_id.0 as i64, DiscordIds::Channel)
+      .map(|(_, project)| (project.id, project.role_id))
+  };
+  let role_id = project.and_then(|(_, role_id)| role_id).map(|id| id as u64);
+  match (role_id, &state) {
+    (Some(role_id), ReadState::Allow) => {
+      http
+        .add_member_role(guild_id.0, user_id.0, role_id, None)
+        .await?
+    }
+    (Some(role_id), ReadState::Deny) => {
+      http
+        .remove_member_role(guild_id.0, user_id.0, role_id, None)
+        .await?
+    }
+    (None, ReadState::Allow) => {
+      let overwrite = member_channel_read(user_id, ReadState::Allow);
+      channel_id.create_permission(http, &overwrite).await?
+    }
+    (None, ReadState::Deny) => {
+      channel_id
+        .delete_permission(http, PermissionOverwriteType::Member(user_id))
+        .await?
+    }
+  }
+
+  if let Some((project_id, _)) = project {
+    let mut db_instance = INSTANCE.write().unwrap();
+    match state {
+      ReadState::Allow => db_instance.project_member_add(project_id, user_id.0),
+      ReadState::Deny => db_instance.project_member_remove(project_id, user_id.0),
+    }
+  }
+  Ok(())
+}
+
+/// Users the permission overwrites of the channel let in
+fn overwrite_members(channel: &GuildChannel) -> HashSet<u64> {
+  channel
+    .permission_overwrites
+    .iter()
+    .filter_map(|overwrite| match overwrite.kind {
+      PermissionOverwriteType::Member(user_id)
+        if overwrite.allow.contains(Permissions::VIEW_CHANNEL) =>
+      {
+        Some(user_id.0)
+      }
+      _ => None,
+    })
+    .collect()
+}
+
+/// Users to add and to remove so the roster matches the actual members
+fn roster_changes(roster: &HashSet<u64>, actual: &HashSet<u64>) -> (Vec<u64>, Vec<u64>) {
+  (
+    actual.difference(roster).copied().collect(),
+    roster.difference(actual).copied().collect(),
+  )
+}
+
+/// Record the members given access before the roster existed or outside the bot.
+/// A project with a role only changes through the bot, its members aren't all cached.
+fn sync_roster(project: &Project, channel: &GuildChannel) {
+  if project.role_id.is_some() {
+    return;
+  }
+  let mut db_instance = INSTANCE.write().unwrap();
+  let roster: HashSet<u64> = db_instance
+    .project_members_of(project.id)
+    .iter()
+    .map(|member| member.user_id as u64)
+    .collect();
+  let (added, removed) = roster_changes(&roster, &overwrite_members(channel));
+  for user_id in added {
+    db_instance.project_member_add(project.id, user_id);
+  }
+  for user_id in removed {
+    db_instance.project_member_remove(project.id, user_id);
+  }
+}
+
+/// The project of the channel with its synced roster
+fn project_roster(channel_id: ChannelId, cache: &Cache) -> Result<(Project, Vec<UserId>), String> {
+  let project = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .projects_search(channel_id.0 as i64, DiscordIds::Channel)
+      .map(|(_, project)| project.clone())
+  }
+  .ok_or_else(|| format!("{} isn't a project", channel_id.mention()))?;
+  if let Some(channel) = cache.guild_channel(channel_id) {
+    sync_roster(&project, &channel);
+  }
+  let db_instance = INSTANCE.read().unwrap();
+  let members = db_instance
+    .project_members_of(project.id)
+    .iter()
+    .map(|member| UserId(member.user_id as u64))
+    .collect();
+  Ok((project, members))
+}
+
+#[command(
+  name = "project-members",
+  usage = "@BOT project-members [<#channel>]",
+  max_args = 1,
+  permission = User
+)]
+pub async fn list_members(params: CallBackParams) -> CallbackReturn {
+  let project_chan = params
+    .arguments()
+    .optional::<ChannelId>()?
+    .unwrap_or(params.message.channel_id);
+  let project = match project_roster(project_chan, &params.context.cache) {
+    Ok((project, _)) => project,
+    Err(error) => return Ok(Some(error)),
+  };
+  let db_instance = INSTANCE.read().unwrap();
+  let members = db_instance.project_members_of(project.id);
+  let mut display = format!(
+    "Members of {} ({}):\n",
+    project_chan.mention(),
+    members.len()
+  );
+  for member in members {
+    writeln!(
+      display,
+      "{} since {}",
+      UserId(member.user_id as u64).mention(),
+      format_date(member.joined_at, params.message.author.id)
+    )
+    .expect("unable to append in string");
+  }
+  Ok(Some(display))
+}
+
+/// Argument of `project-add`
+enum UserOrRole {
+  User(UserId),
+  Role(RoleId),
+}
+
+impl FromArgument for UserOrRole {
+  const EXPECTED: &'static str = "expected a user or a role: <@user> or <@&role>";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    UserId::from_argument(argument)
+      .map(UserOrRole::User)
+      .or_else(|_| RoleId::from_argument(argument).map(UserOrRole::Role))
+      .map_err(|_| String::from(Self::EXPECTED))
+  }
+}
+
+#[command(
+  name = "project-add",
+  usage = "@BOT project-add <@user | @role>...",
+  min_args = 1,
+  max_args = 100,
+  permission = User
+)]
+pub async fn add_members(params: CallBackParams) -> CallbackReturn {
+  let channel = match params
+    .context
+    .cache
+    .guild_channel(params.message.channel_id)
+  {
+    Some(channel) => channel,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a project channel",
+      )))
+    }
+  };
+  if let Err(error) = project_roster(channel.id, &params.context.cache) {
+    return Ok(Some(error));
+  }
+  // The members of a role are added one by one, later members of the role aren't added
+  let mut users: Vec<UserId> = Vec::new();
+  for mention in params.arguments().many::<UserOrRole>()? {
+    match mention {
+      UserOrRole::User(user_id) => users.push(user_id),
+      UserOrRole::Role(role_id) => {
+        if let Some(guild) = params.context.cache.guild(channel.guild_id) {
+          users.extend(
+            guild
+              .members
+              .values()
+              .filter(|member| member.roles.contains(&role_id))
+              .map(|member| member.user.id),
+          );
+        }
+      }
+    }
+  }
+  users.sort();
+  users.dedup();
+  for user_id in users.iter() {
+    set_access(
+      &params.context.http,
+      channel.guild_id,
+      channel.id,
+      *user_id,
+      ReadState::Allow,
+    )
+    .await?;
+  }
+  Ok(Some(format!("Added {} members", users.len())))
+}
+
+#[command(
+  name = "project-copy-members",
+  usage = "@BOT project-copy-members <#other-project>",
+  min_args = 1,
+  permission = User
+)]
+pub async fn copy_members(params: CallBackParams) -> CallbackReturn {
+  let source: ChannelId = params.arguments().next()?;
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a project channel",
+      )))
+    }
+  };
+  let cache = &params.context.cache;
+  let (_, current) = match project_roster(params.message.channel_id, cache) {
+    Ok(roster) => roster,
+    Err(error) => return Ok(Some(error)),
+  };
+  let (_, copied) = match project_roster(source, cache) {
+    Ok(roster) => roster,
+    Err(error) => return Ok(Some(error)),
+  };
+  let added: Vec<UserId> = copied
+    .into_iter()
+    .filter(|user_id| !current.contains(user_id))
+    .collect();
+  for user_id in added.iter() {
+    set_access(
+      &params.context.http,
+      guild_id,
+      params.message.channel_id,
+      *user_id,
+      ReadState::Allow,
+    )
+    .await?;
+  }
+  Ok(Some(format!(
+    "Added {} members of {}",
+    added.len(),
+    source.mention()
+  )))
+}
+
+/// Remove the user from every project of the guild they are a member of
+pub async fn remove_from_all(
+  http: &Http,
+  cache: &Cache,
+  guild_id: GuildId,
+  user_id: UserId,
+) -> Result<usize, SerenityError> {
+  let channels: Vec<ChannelId> = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .projects
+      .iter()
+      .map(|project| ChannelId(project.channel_id as u64))
+      .filter(|channel_id| {
+        cache
+          .guild_channel(*channel_id)
+          .map(|channel| channel.guild_id)
+          == Some(guild_id)
+      })
+      .collect()
+  };
+  let mut removed = 0;
+  for channel_id in channels {
+    let is_member = match project_roster(channel_id, cache) {
+      Ok((_, members)) => members.contains(&user_id),
+      Err(_) => false,
+    };
+    if is_member {
+      set_access(http, guild_id, channel_id, user_id, ReadState::Deny).await?;
+      removed += 1;
+    }
+  }
+  Ok(removed)
+}
+
+#[test]
+fn test_roster_changes() {
+  let roster = HashSet::from([1, 2, 3]);
+  let actual = HashSet::from([2, 3, 4]);
+  assert_eq!(roster_changes(&roster, &actual), (vec![4], vec![1]));
+  assert_eq!(roster_changes(&actual, &actual), (vec![], vec![]));
+}
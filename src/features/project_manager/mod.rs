@@ -0,0 +1,670 @@
+use std::{collections::HashMap, error::Error, sync::Arc};
+
+use crate::core::{
+  arguments::{ArgumentError, Arguments, FromArgument},
+  commands::{CallBackParams, CallbackReturn},
+  config::{self, ConfigKey},
+  parse,
+  permissions::{member_channel_read, ReadState},
+  timezone::{user_now, user_timezone},
+};
+use crate::{
+  core::parse::DiscordIds,
+  database::{NewProject, Project, ProjectState, ProjectUpdate, INSTANCE},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use futures::FutureExt;
+use log::error;
+use procedural_macros::command;
+use serenity::{
+  http::Http,
+  model::{
+    channel::{ChannelType, GuildChannel, Message, Reaction},
+    guild::Guild,
+    id::{ChannelId, GuildId, UserId},
+  },
+  prelude::*,
+  Error as SerenityError,
+};
+use template::ProjectResources;
+
+pub mod deadline;
+pub mod export;
+pub mod listing;
+pub mod members;
+pub mod state;
+pub mod subscription;
+pub mod template;
+
+const ARGUMENT_LIST: [&str; 6] = [
+  "codex",
+  "client",
+  "lead",
+  "deadline",
+  "description",
+  "contexte",
+];
+/// Only a created project can use a template
+const CREATE_ARGUMENT_LIST: [&str; 7] = [
+  "codex",
+  "client",
+  "lead",
+  "deadline",
+  "description",
+  "contexte",
+  "template",
+];
+
+pub fn project_creation_args<'a>(
+  arguments: &mut Arguments<'a>,
+  allowed: &[&str],
+) -> Result<HashMap<&'a str, &'a str>, ArgumentError> {
+  let name = arguments.next_raw()?;
+  let mut project_args = arguments.key_values(allowed)?;
+  project_args.insert("name", name);
+  Ok(project_args)
+}
+
+fn state_label(state: ProjectState) -> &'static str {
+  match state {
+    ProjectState::Active => "En cours",
+    ProjectState::Paused => "En pause",
+    ProjectState::Closed => "Clôturé",
+  }
+}
+
+/// Content of the announcement and pinned messages of a project
+fn project_card(
+  project_chan: ChannelId,
+  created_at: DateTime<Tz>,
+  state: ProjectState,
+  fields: &HashMap<&str, &str>,
+) -> String {
+  let field = |name| fields.get(name).copied().unwrap_or("N/A");
+  format!(
+    "Création de <#{}>.
+
+**Fiche de projet**
+---
+**Date de création** : {}
+**Statut** : {}
+**Client** : {}
+**Codex** : {}
+**Lead projet** : {}
+**Deadline (si applicable)** : {}
+**Brief projet** : {}
+**Contexte projet** : {}
+  ",
+    project_chan.0,
+    created_at.format("%d/%m/%Y"),
+    state_label(state),
+    field("client"),
+    field("codex"),
+    field("lead"),
+    field("deadline"),
+    field("description"),
+    field("contexte"),
+  )
+}
+
+fn project_fields(project: &Project) -> HashMap<&str, &str> {
+  HashMap::from([
+    ("codex", &*project.codex),
+    ("client", &*project.client),
+    ("lead", &*project.lead),
+    ("deadline", &*project.deadline),
+    ("description", &*project.description),
+    ("contexte", &*project.contexte),
+  ])
+}
+
+/// Render the card of the project again in its announcement and pinned messages
+pub async fn refresh_project_card(
+  project: &Project,
+  announcement_channel: ChannelId,
+  timezone: Tz,
+  http: &Http,
+) -> Result<(), SerenityError> {
+  let created_at: DateTime<Utc> = project.created_at.into();
+  let content = project_card(
+    ChannelId(project.channel_id as u64),
+    created_at.with_timezone(&timezone),
+    project.state(),
+    &project_fields(project),
+  );
+  announcement_channel
+    .edit_message(http, project.message_id as u64, |message| {
+      message.content(&content)
+    })
+    .await?;
+  if let Some(pinned_message_id) = project.pinned_message_id {
+    ChannelId(project.channel_id as u64)
+      .edit_message(http, pinned_message_id as u64, |message| {
+        message.content(&content)
+      })
+      .await?;
+  }
+  Ok(())
+}
+
+fn project_init<'fut>(
+  project_args: HashMap<&'fut str, &'fut str>,
+  deadline_date: Option<NaiveDate>,
+  project_chan: ChannelId,
+  resources: ProjectResources,
+  announcement_channel: ChannelId,
+  message: &'fut Message,
+  http: &'fut Arc<Http>,
+) -> CallbackReturn<'fut> {
+  async move {
+    let datetime = user_now(message.author.id);
+
+    // The author of a templated project already has its role
+    if resources.role.is_none() {
+      let overwrite = member_channel_read(message.author.id, ReadState::Allow);
+      project_chan.create_permission(http, &overwrite).await?;
+    }
+
+    // The lead is mentioned so the deadline reminders ping them
+    let author_mention = message.author.mention().to_string();
+    let mut project_args: HashMap<&str, &str> = project_args;
+    project_args.entry("client").or_insert("");
+    project_args.entry("codex").or_insert("#PXXX");
+    project_args.entry("lead").or_insert(&author_mention);
+    let content = &project_card(project_chan, datetime, ProjectState::Active, &project_args);
+    let field = |name| project_args.get(name).copied().or(Some("N/A"));
+    let annoucement_message = announcement_channel.say(http, content).await?;
+    let channel_message = project_chan.say(http, content).await?;
+    channel_message.pin(http).await?;
+    {
+      let mut db_instance = INSTANCE.write().unwrap();
+      db_instance.project_add(NewProject {
+        message_id: annoucement_message.id.0 as i64,
+        channel_id: project_chan.0 as i64,
+        pinned_message_id: Some(channel_message.id.0 as i64),
+        codex: field("codex"),
+        client: field("client"),
+        lead: field("lead"),
+        deadline: field("deadline"),
+        description: field("description"),
+        contexte: field("contexte"),
+        deadline_date,
+        role_id: resources.role.map(|role| role.0 as i64),
+        voice_channel_id: resources.voice_channel.map(|channel| channel.0 as i64),
+        thread_ids: resources
+          .threads
+          .iter()
+          .map(|thread| thread.0 as i64)
+          .collect(),
+      });
+    }
+    let project = {
+      let db_instance = INSTANCE.read().unwrap();
+      db_instance
+        .projects_search(project_chan.0 as i64, DiscordIds::Channel)
+        .map(|(_, project)| project.clone())
+    };
+    if let Some(project) = project {
+      {
+        let mut db_instance = INSTANCE.write().unwrap();
+        db_instance.project_member_add(project.id, message.author.id.0);
+      }
+      deadline::schedule_deadline_reminders(&project, message.author.id);
+    }
+    annoucement_message.react(http, '✅').await?;
+    if message.channel_id == announcement_channel {
+      message.delete(http).await?;
+      return Ok(None);
+    }
+    Ok(Some(String::from(":ok:")))
+  }
+  .boxed()
+}
+
+#[command(
+  name = "create-project",
+  usage = "@BOT create-project <name> [codex=<codex>, client=<client>, lead=<Lead>, deadline=<Deadline>, description=<Brief projet>, contexte=<Contexte>, template=<template>]",
+  min_args = 1,
+  max_args = 8,
+  permission = User
+)]
+pub async fn create(params: CallBackParams) -> CallbackReturn {
+  let mut project_args = project_creation_args(&mut params.arguments(), &CREATE_ARGUMENT_LIST)?;
+  let deadline_date = match deadline::parse_deadline(project_args.get("deadline").copied()) {
+    Ok(deadline_date) => deadline_date,
+    Err(error) => return Ok(Some(error)),
+  };
+  let guild = match parse::get_guild(params.message.channel_id, params.context, None).await {
+    Ok(guild) => guild,
+    Err(error) => return Ok(Some(error)),
+  };
+  let template = match project_args.remove("template") {
+    Some(name) => match template::find_template(guild, name) {
+      Some(template) => Some(template),
+      None => {
+        return Ok(Some(format!(
+          "There is no template named {}, see @BOT project-template list",
+          name
+        )))
+      }
+    },
+    None => None,
+  };
+  let project_category = config::require(guild, ConfigKey::ProjectCategory)?;
+  let announcement_channel = config::require(guild, ConfigKey::ProjectAnnouncementChannel)?;
+  let http = &params.context.http;
+  let newchan = guild
+    .create_channel(http, |channel| {
+      channel
+        .kind(ChannelType::Text)
+        .category(project_category)
+        .name(project_args["name"])
+    })
+    .await?;
+  let resources = match &template {
+    Some(template) => {
+      let result = template::create_resources(
+        template,
+        guild,
+        newchan.id,
+        project_args["name"],
+        ChannelId(project_category),
+        params.message.author.id,
+        http,
+      )
+      .await;
+      match result {
+        Ok(resources) => resources,
+        Err(why) => {
+          delete_unrecorded_project(guild, newchan.id, &ProjectResources::default(), http).await;
+          return Err(why.into());
+        }
+      }
+    }
+    None => ProjectResources::default(),
+  };
+
+  let result = project_init(
+    project_args,
+    deadline_date,
+    newchan.id,
+    resources.clone(),
+    ChannelId(announcement_channel),
+    params.message,
+    &params.context.http,
+  )
+  .await;
+  if result.is_err() {
+    delete_unrecorded_project(guild, newchan.id, &resources, http).await;
+  }
+  result
+}
+
+/// Delete the channel and the resources of a project that failed before being recorded
+async fn delete_unrecorded_project(
+  guild: GuildId,
+  channel: ChannelId,
+  resources: &ProjectResources,
+  http: &Http,
+) {
+  let recorded = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .projects_search(channel.0 as i64, DiscordIds::Channel)
+      .is_some()
+  };
+  if recorded {
+    return;
+  }
+  if let Err(why) = channel.delete(http).await {
+    error!("Unable to delete the channel {}: {}", channel, why);
+  }
+  resources.delete(guild, http).await;
+}
+
+#[command(
+  name = "add-project",
+  usage = "@BOT add-project <#channel_id> <name> [codex=<codex>, client=<client>, lead=<Lead>, deadline=<Deadline>, description=<Brief projet>, contexte=<Contexte>]",
+  min_args = 2,
+  max_args = 8,
+  permission = User
+)]
+pub async fn add(params: CallBackParams<'_>) -> CallbackReturn {
+  let mut arguments = params.arguments();
+  let project_chan: ChannelId = arguments.next()?;
+  let project_args = project_creation_args(&mut arguments, &ARGUMENT_LIST)?;
+  let deadline_date = match deadline::parse_deadline(project_args.get("deadline").copied()) {
+    Ok(deadline_date) => deadline_date,
+    Err(error) => return Ok(Some(error)),
+  };
+  let guild = match parse::get_guild(project_chan, params.context, None).await {
+    Ok(guild) => guild,
+    Err(error) => return Ok(Some(error)),
+  };
+  let announcement_channel = config::require(guild, ConfigKey::ProjectAnnouncementChannel)?;
+
+  project_init(
+    project_args,
+    deadline_date,
+    project_chan,
+    ProjectResources::default(),
+    ChannelId(announcement_channel),
+    params.message,
+    &params.context.http,
+  )
+  .await
+}
+
+#[command(
+  name = "project-edit",
+  usage = "@BOT project-edit [codex=<codex>, client=<client>, lead=<Lead>, deadline=<Deadline>, description=<Brief projet>, contexte=<Contexte>]",
+  min_args = 1,
+  max_args = 6,
+  permission = User
+)]
+pub async fn edit(params: CallBackParams) -> CallbackReturn {
+  let fields = params.arguments().key_values(&ARGUMENT_LIST)?;
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a project channel",
+      )))
+    }
+  };
+  let announcement_channel = config::require(guild_id, ConfigKey::ProjectAnnouncementChannel)?;
+  let deadline_date = match fields.get("deadline") {
+    Some(deadline) => match deadline::parse_deadline(Some(deadline)) {
+      Ok(deadline_date) => Some(deadline_date),
+      Err(error) => return Ok(Some(error)),
+    },
+    None => None,
+  };
+  let changes = ProjectUpdate {
+    codex: fields.get("codex").copied(),
+    client: fields.get("client").copied(),
+    lead: fields.get("lead").copied(),
+    deadline: fields.get("deadline").copied(),
+    description: fields.get("description").copied(),
+    contexte: fields.get("contexte").copied(),
+    deadline_date,
+    ..Default::default()
+  };
+  let project = {
+    let mut db_instance = INSTANCE.write().unwrap();
+    match db_instance.project_update(params.message.channel_id.0, &changes)? {
+      Some(project) => project,
+      None => {
+        return Ok(Some(String::from(
+          "This command is restricted to a project channel",
+        )))
+      }
+    }
+  };
+  if changes.deadline_date.is_some() || changes.lead.is_some() {
+    deadline::schedule_deadline_reminders(&project, params.message.author.id);
+  }
+  refresh_project_card(
+    &project,
+    ChannelId(announcement_channel),
+    user_timezone(params.message.author.id),
+    &params.context.http,
+  )
+  .await?;
+  Ok(Some(String::from(":ok:")))
+}
+
+#[command(
+  name = "delete-project",
+  usage = "@BOT delete-project <name>",
+  min_args = 1,
+  permission = User
+)]
+pub async fn delete(params: CallBackParams) -> CallbackReturn {
+  let target: ChannelId = params.arguments().next()?;
+  let guild = match parse::get_guild(target, params.context, None).await {
+    Ok(guild) => guild,
+    Err(error) => return Ok(Some(error)),
+  };
+  let announcement_channel = config::require(guild, ConfigKey::ProjectAnnouncementChannel)?;
+  let resultcpy;
+  {
+    let mut db_instance = INSTANCE.write().unwrap();
+    let result = db_instance.projects_delete(target.0)?;
+    resultcpy = (String::from(result.0), result.1);
+  }
+  if let Some(project) = resultcpy.1 {
+    let http = &params.context.http;
+    ChannelId(project.channel_id as u64).delete(http).await?;
+    template::delete_resources(&project, guild, http).await?;
+    ChannelId(announcement_channel)
+      .message(http, project.message_id as u64)
+      .await?
+      .delete(http)
+      .await?;
+  };
+
+  Ok(Some(resultcpy.0))
+}
+
+async fn create_read_permission(
+  context: &Context,
+  guildchannel: &GuildChannel,
+  userid: u64,
+  state: ReadState,
+) -> Result<Option<String>, String> {
+  let message = if let &ReadState::Allow = &state {
+    Ok(Some(format!("Added <@{}> Welcome !", userid)))
+  } else {
+    Ok(Some(format!("Removed <@{}>", userid)))
+  };
+  let is_project = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .projects_search(guildchannel.id.0 as i64, DiscordIds::Channel)
+      .is_some()
+  };
+  if is_project {
+    return members::set_access(
+      &context.http,
+      guildchannel.guild_id,
+      guildchannel.id,
+      UserId(userid),
+      state,
+    )
+    .await
+    .map_err(|why| why.to_string())
+    .and(message);
+  }
+  let overwrite = member_channel_read(UserId(userid), state);
+  guildchannel
+    .create_permission(&context.http, &overwrite)
+    .await
+    .unwrap();
+  message
+}
+
+pub async fn user_view(
+  params: CallBackParams<'_>,
+  state: ReadState,
+) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+  let usertag = params.arguments().next_raw()?;
+  let guildchannel = match params
+    .context
+    .cache
+    .guild_channel(params.message.channel_id)
+  {
+    Some(guildchannel) => guildchannel,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a guild channel",
+      )))
+    }
+  };
+  // A mention, else the name of the member or a part of it
+  let result = match UserId::from_argument(usertag) {
+    Ok(userid) => create_read_permission(params.context, &guildchannel, userid.0, state).await,
+    Err(_) => match guildchannel.guild(params.context) {
+      Some(guild) => match guild.member_named(usertag) {
+        Some(member) => {
+          let userid = member.user.id.0;
+          create_read_permission(params.context, &guildchannel, userid, state).await
+        }
+        None => check_containing(params.context, &guild, usertag, guildchannel).await,
+      },
+      None => {
+        return Ok(Some(String::from(
+          "Unable to find the guild of the channel",
+        )))
+      }
+    },
+  };
+  Ok(result?)
+}
+
+#[command(
+  name = "remove",
+  usage = "@BOT remove <@user>",
+  min_args = 1,
+  permission = User
+)]
+pub async fn remove_user(params: CallBackParams<'_>) -> CallbackReturn<'_> {
+  user_view(params, ReadState::Deny).await
+}
+
+#[command(
+  name = "add",
+  usage = "@BOT add <@user>",
+  min_args = 1,
+  permission = User
+)]
+pub async fn add_user(params: CallBackParams<'_>) -> CallbackReturn<'_> {
+  user_view(params, ReadState::Allow).await
+}
+
+async fn check_containing(
+  context: &Context,
+  guild: &Guild,
+  usertag: &str,
+  guildchannel: GuildChannel,
+) -> Result<Option<String>, String> {
+  let members = guild.members_containing(usertag, false, false).await;
+  if !members.is_empty() {
+    if members.len() == 1 {
+      let userid = members[0].0.user.id.0;
+      create_read_permission(context, &guildchannel, userid, ReadState::Allow).await
+    } else {
+      let membernames: String = members
+        .iter()
+        .map(|member| format!("{}, ", member.1))
+        .collect();
+      Ok(Some(format!(
+        "Found too many users with this name: {},\n found: {}",
+        usertag, membernames
+      )))
+    }
+  } else {
+    Ok(Some(format!(
+      "Didn't find any user with {} in their name",
+      usertag
+    )))
+  }
+}
+
+pub async fn check_subscribe(ctx: &Context, reaction: &Reaction, removed: bool) {
+  let mut project_chanid = 0;
+  {
+    let db_instance = INSTANCE.read().unwrap();
+    if let Some((_index, project)) =
+      db_instance.projects_search(reaction.message_id.0 as i64, parse::DiscordIds::Message)
+    {
+      // A closed project can't be joined from its announcement
+      if project.state() != ProjectState::Closed {
+        project_chanid = project.channel_id;
+      }
+    }
+  }
+
+  if project_chanid > 0 {
+    if let Some(channel) = ctx.cache.guild_channel(project_chanid as u64) {
+      let user_id = reaction.user_id.unwrap();
+      let state = if removed {
+        ReadState::Deny
+      } else {
+        ReadState::Allow
+      };
+      let result =
+        members::set_access(&ctx.http, channel.guild_id, channel.id, user_id, state).await;
+      if let Err(why) = result {
+        error!("Unable to change the access of {}: {}", user_id, why);
+      }
+    } else {
+      error!("Unable to find project channel in cache");
+    }
+  }
+}
+
+/// Channels in the project category
+async fn list_projects(guild_id: GuildId, context: &Context) -> Vec<(ChannelId, GuildChannel)> {
+  let project_category = config::get(guild_id, ConfigKey::ProjectCategory);
+  let announcement_channel = config::get(guild_id, ConfigKey::ProjectAnnouncementChannel);
+  let text_projects_channels: Vec<_> = guild_id
+    .channels(&context.http)
+    .await
+    .unwrap()
+    .iter()
+    .filter(|(_, chan)| {
+      chan.kind == ChannelType::Text
+        && match chan.parent_id {
+          Some(category) => {
+            Some(category.0) == project_category && Some(chan.id.0) != announcement_channel
+          }
+          _ => false,
+        }
+    })
+    .map(|e| (*e.0, e.1.clone()))
+    .collect();
+
+  text_projects_channels
+}
+
+#[command(
+  name = "project-clear-user",
+  usage = "@BOT project-clear-user <User>",
+  min_args = 1,
+  permission = Admin
+)]
+pub async fn remove_user_from_all(params: CallBackParams<'_>) -> CallbackReturn {
+  let user_id: UserId = params.arguments().next()?;
+  let guild = params
+    .message
+    .guild_id
+    .expect("Unable to find guildid in message");
+  let removed =
+    members::remove_from_all(&params.context.http, &params.context.cache, guild, user_id).await?;
+  Ok(Some(format!(
+    "Removed {} from {} projects",
+    user_id.mention(),
+    removed
+  )))
+}
+
+#[test]
+fn test_project_card() {
+  use chrono::TimeZone;
+  use chrono_tz::Europe::Paris;
+
+  let fields = HashMap::from([("client", "ACME"), ("codex", "#P042")]);
+  let card = project_card(
+    ChannelId(42),
+    Paris.ymd(2022, 10, 30).and_hms(18, 0, 0),
+    ProjectState::Paused,
+    &fields,
+  );
+  assert!(card.starts_with("Création de <#42>."));
+  assert!(card.contains("**Date de création** : 30/10/2022"));
+  assert!(card.contains("**Statut** : En pause"));
+  assert!(card.contains("**Client** : ACME"));
+  assert!(card.contains("**Deadline (si applicable)** : N/A"));
+}
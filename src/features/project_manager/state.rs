@@ -0,0 +1,164 @@
+//! Lifecycle of a project: active, paused or closed.
+//!
+//! A closed project is moved to the archive category through the archive log, it can't be
+//! joined from its announcement anymore and it isn't moved back when a message is sent in
+//! its channel.
+use super::{deadline, refresh_project_card};
+use crate::features::archivage::history;
+use crate::{
+  core::{
+    commands::{CallBackParams, CallbackReturn},
+    config::{self, ConfigKey},
+    parse::DiscordIds,
+    timezone::user_timezone,
+  },
+  database::{ProjectState, ProjectUpdate, INSTANCE},
+};
+use procedural_macros::command;
+use serenity::{model::id::ChannelId, prelude::Mentionable};
+use std::error::Error;
+
+/// Whether the channel is the one of a closed project
+pub fn is_closed(channel_id: ChannelId) -> bool {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance.projects.iter().any(|project| {
+    project.channel_id == channel_id.0 as i64 && project.state() == ProjectState::Closed
+  })
+}
+
+/// The project is the one of the channel given as argument, or the current channel
+async fn change_state(
+  params: &CallBackParams<'_>,
+  state: ProjectState,
+) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a guild channel",
+      )))
+    }
+  };
+  let project_chan = params
+    .arguments()
+    .optional::<ChannelId>()?
+    .unwrap_or(params.message.channel_id);
+  let announcement_channel = ChannelId(config::require(
+    guild_id,
+    ConfigKey::ProjectAnnouncementChannel,
+  )?);
+  let category = match state {
+    ProjectState::Active => Some(config::require(guild_id, ConfigKey::ProjectCategory)?),
+    ProjectState::Paused => None,
+    ProjectState::Closed => Some(config::require(guild_id, ConfigKey::ArchiveCategory)?),
+  };
+  let project = {
+    let db_instance = INSTANCE.read().unwrap();
+    db_instance
+      .projects_search(project_chan.0 as i64, DiscordIds::Channel)
+      .map(|(_, project)| project.clone())
+  };
+  let mut project = match project {
+    Some(project) => project,
+    None => return Ok(Some(format!("{} isn't a project", project_chan.mention()))),
+  };
+  if project.state() == state {
+    return Ok(Some(format!(
+      "{} is already {}",
+      project_chan.mention(),
+      state
+    )));
+  }
+  let channel = match params.context.cache.guild_channel(project_chan) {
+    Some(channel) => channel,
+    None => {
+      return Ok(Some(format!(
+        "{} isn't a channel of the guild",
+        project_chan.mention()
+      )))
+    }
+  };
+
+  // The state is saved once Discord is up to date, a failure leaves the project unchanged
+  let http = &params.context.http;
+  if let Some(category) = category {
+    // The archive log keeps where the channel was, along with its transcript
+    let author = params.message.author.id;
+    if state == ProjectState::Closed {
+      history::archive_channel(http, &channel, ChannelId(category), Some(author)).await?;
+    } else {
+      history::restore_channel(http, &channel, Some(category), Some(author)).await?;
+    }
+    if let Some(voice_channel) = project.voice_channel_id {
+      ChannelId(voice_channel as u64)
+        .edit(http, |channel| channel.category(ChannelId(category)))
+        .await?;
+    }
+  }
+  // The starter threads of a closed project are locked until it's reopened
+  let closed = state == ProjectState::Closed;
+  for thread_id in project.thread_ids.iter() {
+    ChannelId(*thread_id as u64)
+      .edit_thread(http, |thread| thread.archived(closed).locked(closed))
+      .await?;
+  }
+  let announcement = announcement_channel
+    .message(http, project.message_id as u64)
+    .await?;
+  if closed {
+    announcement.delete_reaction_emoji(http, '✅').await?;
+  } else {
+    announcement.react(http, '✅').await?;
+  }
+  project.state = state.to_string();
+  refresh_project_card(
+    &project,
+    announcement_channel,
+    user_timezone(params.message.author.id),
+    http,
+  )
+  .await?;
+  let project = {
+    let mut db_instance = INSTANCE.write().unwrap();
+    let changes = ProjectUpdate {
+      state: Some(&project.state),
+      ..Default::default()
+    };
+    db_instance.project_update(project_chan.0, &changes)?
+  };
+  // Closed projects don't have deadline reminders
+  if let Some(project) = project {
+    deadline::schedule_deadline_reminders(&project, params.message.author.id);
+  }
+  Ok(Some(String::from(":ok:")))
+}
+
+#[command(
+  name = "project-close",
+  usage = "@BOT project-close [<#channel>]",
+  max_args = 1,
+  permission = User
+)]
+pub async fn close(params: CallBackParams) -> CallbackReturn {
+  change_state(&params, ProjectState::Closed).await
+}
+
+#[command(
+  name = "project-pause",
+  usage = "@BOT project-pause [<#channel>]",
+  max_args = 1,
+  permission = User
+)]
+pub async fn pause(params: CallBackParams) -> CallbackReturn {
+  change_state(&params, ProjectState::Paused).await
+}
+
+#[command(
+  name = "project-reopen",
+  usage = "@BOT project-reopen [<#channel>]",
+  max_args = 1,
+  permission = User
+)]
+pub async fn reopen(params: CallBackParams) -> CallbackReturn {
+  change_state(&params, ProjectState::Active).await
+}
@@ -0,0 +1,273 @@
+//! Persistent message listing the active projects in select menus.
+//!
+//! Choosing a project toggles the access of the user to its channel. The messages are
+//! stored as [`StorageDataType::ProjectBottomMessage`] with the id of their channel,
+//! they are refreshed when a channel of the guild is created, moved or deleted.
+use super::{list_projects, members, template};
+use crate::{
+  core::{
+    commands::{CallBackParams, CallbackReturn},
+    config::{self, ConfigKey},
+    permissions::ReadState,
+  },
+  database::{NewStorage, StorageDataType, INSTANCE},
+};
+use log::error;
+use procedural_macros::command;
+use serenity::{
+  builder::CreateComponents,
+  model::{
+    application::interaction::{
+      message_component::MessageComponentInteraction, InteractionResponseType,
+    },
+    channel::{GuildChannel, PermissionOverwriteType},
+    id::{ChannelId, GuildId, MessageId},
+    Permissions,
+  },
+  prelude::*,
+};
+use std::time::SystemTime;
+
+pub const SUBSCRIBE_ID: &str = "subscribe";
+/// Discord limits of the select menus
+const OPTIONS_PER_MENU: usize = 25;
+const MENUS_MAX: usize = 5;
+const PLACEHOLDER_MAX_LEN: usize = 150;
+
+fn subscription_content(projects_count: usize) -> String {
+  match projects_count {
+    0 => String::from("No active project"),
+    count if count > OPTIONS_PER_MENU * MENUS_MAX => format!(
+      "Choose a project to join or leave it, only the first {} projects are listed",
+      OPTIONS_PER_MENU * MENUS_MAX
+    ),
+    _ => String::from("Choose a project to join or leave it"),
+  }
+}
+
+/// Names of the first and last projects of the menu, shortened to fit in a placeholder
+fn menu_placeholder(first: &str, last: &str) -> String {
+  let name_max_len = (PLACEHOLDER_MAX_LEN - " - ".len()) / 2;
+  let shorten = |name: &str| {
+    if name.chars().count() <= name_max_len {
+      return name.to_string();
+    }
+    let mut short: String = name.chars().take(name_max_len - 1).collect();
+    short.push('…');
+    short
+  };
+  format!("{} - {}", shorten(first), shorten(last))
+}
+
+fn subscription_menus(projects: &[GuildChannel]) -> CreateComponents {
+  let mut components = CreateComponents::default();
+  for (index, chunk) in projects
+    .chunks(OPTIONS_PER_MENU)
+    .take(MENUS_MAX)
+    .enumerate()
+  {
+    components.create_action_row(|row| {
+      row.create_select_menu(|menu| {
+        menu
+          .custom_id(format!("{}:{}", SUBSCRIBE_ID, index))
+          .placeholder(menu_placeholder(
+            &chunk[0].name,
+            &chunk[chunk.len() - 1].name,
+          ))
+          .options(|options| {
+            for channel in chunk {
+              options.create_option(|option| option.label(&channel.name).value(channel.id.0));
+            }
+            options
+          })
+      })
+    });
+  }
+  components
+}
+
+/// Active projects sorted by name
+async fn active_projects(guild_id: GuildId, context: &Context) -> Vec<GuildChannel> {
+  let mut projects: Vec<GuildChannel> = list_projects(guild_id, context)
+    .await
+    .into_iter()
+    .map(|(_, channel)| channel)
+    .collect();
+  projects.sort_by_key(|channel| channel.name.to_lowercase());
+  projects
+}
+
+/// Stored subscription messages of the guild with their channel
+fn subscription_messages(guild_id: GuildId, context: &Context) -> Vec<(i32, ChannelId, MessageId)> {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .filter_storage_type(StorageDataType::ProjectBottomMessage)
+    .into_iter()
+    .filter_map(|stored| {
+      let channel_id = ChannelId(stored.data.parse().ok()?);
+      let channel = context.cache.guild_channel(channel_id)?;
+      if channel.guild_id != guild_id {
+        return None;
+      }
+      Some((stored.id, channel_id, MessageId(stored.dataid? as u64)))
+    })
+    .collect()
+}
+
+#[command(
+  name = "project-subscription-list",
+  usage = "@BOT project-subscription-list [<#channel>]",
+  max_args = 1,
+  permission = Admin
+)]
+pub async fn post_subscription_list(params: CallBackParams) -> CallbackReturn {
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a guild channel",
+      )))
+    }
+  };
+  let channel_id = match params.arguments().optional::<ChannelId>()? {
+    Some(channel_id) => channel_id,
+    None => ChannelId(config::require(
+      guild_id,
+      ConfigKey::ProjectAnnouncementChannel,
+    )?),
+  };
+  let http = &params.context.http;
+
+  // There is a single list per guild
+  let previous = subscription_messages(guild_id, params.context);
+  for (_, channel_id, message_id) in previous.iter() {
+    if let Err(why) = channel_id.delete_message(http, message_id).await {
+      error!("Unable to delete the subscription list: {}", why);
+    }
+  }
+  {
+    let mut db_instance = INSTANCE.write().unwrap();
+    db_instance.storage_delete(previous.iter().map(|(id, _, _)| *id).collect());
+  }
+
+  let projects = active_projects(guild_id, params.context).await;
+  let message = channel_id
+    .send_message(http, |message| {
+      message
+        .content(subscription_content(projects.len()))
+        .set_components(subscription_menus(&projects))
+    })
+    .await?;
+  let mut db_instance = INSTANCE.write().unwrap();
+  db_instance.storage_add(NewStorage {
+    datatype: StorageDataType::ProjectBottomMessage.into(),
+    dataid: Some(message.id.0 as i64),
+    data: &channel_id.0.to_string(),
+    date: Some(SystemTime::now()),
+  });
+  Ok(Some(String::from(":ok:")))
+}
+
+/// Render the list of the guild again, a list whose message was deleted is forgotten
+pub async fn refresh_subscription_list(guild_id: GuildId, context: &Context) {
+  let messages = subscription_messages(guild_id, context);
+  if messages.is_empty() {
+    return;
+  }
+  let projects = active_projects(guild_id, context).await;
+  for (stored_id, channel_id, message_id) in messages {
+    let result = channel_id
+      .edit_message(&context.http, message_id, |message| {
+        message
+          .content(subscription_content(projects.len()))
+          .set_components(subscription_menus(&projects))
+      })
+      .await;
+    if let Err(why) = result {
+      error!("Unable to refresh the subscription list: {}", why);
+      let mut db_instance = INSTANCE.write().unwrap();
+      db_instance.storage_delete(vec![stored_id]);
+    }
+  }
+}
+
+/// Give or remove the access to the project chosen in the list
+pub async fn toggle_subscription(ctx: &Context, component: &MessageComponentInteraction) {
+  let channel = match component
+    .data
+    .values
+    .first()
+    .and_then(|value| value.parse::<u64>().ok())
+    .and_then(|channel_id| ctx.cache.guild_channel(channel_id))
+  {
+    Some(channel) => channel,
+    None => return error!("Invalid subscription {:?}", component.data.values),
+  };
+  let user_id = component.user.id;
+  let role = template::project_role(channel.id);
+  let is_member = match role {
+    Some(role) => matches!(&component.member, Some(member) if member.roles.contains(&role)),
+    None => channel.permission_overwrites.iter().any(|overwrite| {
+      overwrite.kind == PermissionOverwriteType::Member(user_id)
+        && overwrite.allow.contains(Permissions::VIEW_CHANNEL)
+    }),
+  };
+  let state = if is_member {
+    ReadState::Deny
+  } else {
+    ReadState::Allow
+  };
+  let result = members::set_access(&ctx.http, channel.guild_id, channel.id, user_id, state)
+    .await
+    .map(|_| {
+      if is_member {
+        format!("You left {}", channel.mention())
+      } else {
+        format!("You joined {}", channel.mention())
+      }
+    });
+  let reply = result.unwrap_or_else(|why| {
+    error!("Unable to change the access to {}: {}", channel.name, why);
+    format!("Unable to change your access to {}", channel.mention())
+  });
+  // Rendered again so the menu is reset and the same project can be chosen to leave it
+  let projects = active_projects(channel.guild_id, ctx).await;
+  let result = component
+    .create_interaction_response(&ctx.http, |res| {
+      res
+        .kind(InteractionResponseType::UpdateMessage)
+        .interaction_response_data(|data| {
+          data
+            .content(subscription_content(projects.len()))
+            .set_components(subscription_menus(&projects))
+        })
+    })
+    .await;
+  if let Err(why) = result {
+    error!("Unable to reset the subscription list: {}", why);
+  }
+  let result = component
+    .create_followup_message(&ctx.http, |followup| {
+      followup.content(reply).ephemeral(true)
+    })
+    .await;
+  if let Err(why) = result {
+    error!("Unable to respond to the subscription: {}", why);
+  }
+}
+
+#[test]
+fn test_subscription_content() {
+  assert_eq!(subscription_content(0), "No active project");
+  assert_eq!(
+    subscription_content(12),
+    "Choose a project to join or leave it"
+  );
+  assert!(subscription_content(200).contains("first 125 projects"));
+
+  assert_eq!(menu_placeholder("alpha", "omega"), "alpha - omega");
+  let long_name = "é".repeat(100);
+  let placeholder = menu_placeholder(&long_name, &long_name);
+  assert_eq!(placeholder.chars().count(), PLACEHOLDER_MAX_LEN - 1);
+  assert!(placeholder.ends_with('…'));
+}
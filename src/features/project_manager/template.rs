@@ -0,0 +1,284 @@
+//! Templates of the resources created along a project by `create-project template=<name>`.
+//!
+//! A project created from a template gets a role giving access to its channels instead of
+//! per-member overwrites, optionally a voice channel and starter threads in its text channel.
+use crate::{
+  core::{
+    arguments::{ArgumentError, FromArgument},
+    commands::{CallBackParams, CallbackReturn},
+    permissions::role_channel_read,
+  },
+  database::{NewProjectTemplate, Project, ProjectTemplate, INSTANCE},
+};
+use procedural_macros::command;
+use serenity::{
+  http::Http,
+  model::{
+    channel::ChannelType,
+    id::{ChannelId, GuildId, RoleId, UserId},
+  },
+  Error as SerenityError,
+};
+use std::fmt::Write;
+
+/// Ids of the resources created for a project, all empty without template
+#[derive(Debug, Default, Clone)]
+pub struct ProjectResources {
+  pub role: Option<RoleId>,
+  pub voice_channel: Option<ChannelId>,
+  pub threads: Vec<ChannelId>,
+}
+
+impl ProjectResources {
+  /// Delete the role and the voice channel, the threads go with the text channel
+  pub async fn delete(&self, guild_id: GuildId, http: &Http) {
+    if let Some(voice_channel) = self.voice_channel {
+      if let Err(why) = voice_channel.delete(http).await {
+        error!(
+          "Unable to delete the voice channel {}: {}",
+          voice_channel, why
+        );
+      }
+    }
+    if let Some(role) = self.role {
+      if let Err(why) = guild_id.delete_role(http, role).await {
+        error!("Unable to delete the role {}: {}", role, why);
+      }
+    }
+  }
+}
+
+pub fn find_template(guild_id: GuildId, name: &str) -> Option<ProjectTemplate> {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .project_template_search(guild_id.0, name)
+    .cloned()
+}
+
+/// Role giving access to the project of the channel, when it was created from a template
+pub fn project_role(channel_id: ChannelId) -> Option<RoleId> {
+  let db_instance = INSTANCE.read().unwrap();
+  db_instance
+    .projects
+    .iter()
+    .find(|project| project.channel_id == channel_id.0 as i64)
+    .and_then(|project| project.role_id)
+    .map(|role_id| RoleId(role_id as u64))
+}
+
+/// Option following the name of the template in `project-template set`
+#[derive(Debug, PartialEq)]
+enum TemplateOption {
+  Voice(bool),
+  Threads(Vec<String>),
+}
+
+impl FromArgument for TemplateOption {
+  const EXPECTED: &'static str = "expected voice=<yes|no> or threads=<name1,name2>";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    match argument.split_once('=') {
+      Some(("voice", "yes")) => Ok(TemplateOption::Voice(true)),
+      Some(("voice", "no")) => Ok(TemplateOption::Voice(false)),
+      Some(("threads", threads)) => Ok(TemplateOption::Threads(
+        threads
+          .split(',')
+          .map(str::trim)
+          .filter(|thread| !thread.is_empty())
+          .map(String::from)
+          .collect(),
+      )),
+      _ => Err(String::from(Self::EXPECTED)),
+    }
+  }
+}
+
+fn parse_template(
+  guild_id: GuildId,
+  name: &str,
+  options: Vec<TemplateOption>,
+) -> NewProjectTemplate<'_> {
+  let mut template = NewProjectTemplate {
+    guild_id: guild_id.0 as i64,
+    name,
+    voice_channel: false,
+    threads: Vec::new(),
+  };
+  for option in options {
+    match option {
+      TemplateOption::Voice(voice_channel) => template.voice_channel = voice_channel,
+      TemplateOption::Threads(threads) => template.threads = threads,
+    }
+  }
+  template
+}
+
+fn describe(template: &ProjectTemplate) -> String {
+  let mut resources = vec![String::from("role"), String::from("text channel")];
+  if template.voice_channel {
+    resources.push(String::from("voice channel"));
+  }
+  if !template.threads.is_empty() {
+    resources.push(format!("threads: {}", template.threads.join(", ")));
+  }
+  format!("**{}**: {}", template.name, resources.join(", "))
+}
+
+#[command(
+  name = "project-template",
+  usage = "@BOT project-template <list | set <name> [voice=<yes|no>] [threads=<name1,name2>] | delete <name>>",
+  min_args = 1,
+  max_args = 4,
+  permission = Admin
+)]
+pub async fn template(params: CallBackParams) -> CallbackReturn {
+  let guild_id = match params.message.guild_id {
+    Some(guild_id) => guild_id,
+    None => {
+      return Ok(Some(String::from(
+        "This command is restricted to a guild channel",
+      )))
+    }
+  };
+  let mut arguments = params.arguments();
+  match arguments.next_raw()? {
+    "list" => {
+      let db_instance = INSTANCE.read().unwrap();
+      let mut display = String::new();
+      for template in db_instance
+        .project_templates
+        .iter()
+        .filter(|template| template.guild_id == guild_id.0 as i64)
+      {
+        writeln!(display, "{}", describe(template)).expect("unable to append in string");
+      }
+      if display.is_empty() {
+        display = String::from("No project template");
+      }
+      Ok(Some(display))
+    }
+    "set" => {
+      let name = arguments.next_raw()?;
+      let template = parse_template(guild_id, name, arguments.many()?);
+      let mut db_instance = INSTANCE.write().unwrap();
+      db_instance.project_template_set(template);
+      Ok(Some(String::from(":ok:")))
+    }
+    "delete" => {
+      let name = arguments.next_raw()?;
+      let mut db_instance = INSTANCE.write().unwrap();
+      if db_instance.project_template_delete(guild_id.0, name) {
+        Ok(Some(String::from(":ok:")))
+      } else {
+        Ok(Some(format!("There is no template named {}", name)))
+      }
+    }
+    action => Err(
+      ArgumentError {
+        position: 1,
+        value: Some(action.to_string()),
+        reason: String::from("expected list, set or delete"),
+      }
+      .into(),
+    ),
+  }
+}
+
+/// Create the role, voice channel and threads of the template, the author gets the role.
+/// What was created is deleted when a step fails
+pub async fn create_resources(
+  template: &ProjectTemplate,
+  guild_id: GuildId,
+  project_chan: ChannelId,
+  name: &str,
+  category: ChannelId,
+  author: UserId,
+  http: &Http,
+) -> Result<ProjectResources, SerenityError> {
+  let mut resources = ProjectResources::default();
+  let result: Result<(), SerenityError> = async {
+    let role = guild_id
+      .create_role(http, |role| role.name(name).mentionable(true))
+      .await?;
+    resources.role = Some(role.id);
+    let overwrite = role_channel_read(role.id);
+    project_chan.create_permission(http, &overwrite).await?;
+    http
+      .add_member_role(guild_id.0, author.0, role.id.0, None)
+      .await?;
+
+    if template.voice_channel {
+      let channel = guild_id
+        .create_channel(http, |channel| {
+          channel
+            .kind(ChannelType::Voice)
+            .category(category)
+            .name(name)
+        })
+        .await?;
+      resources.voice_channel = Some(channel.id);
+      channel.create_permission(http, &overwrite).await?;
+    }
+
+    for thread_name in template.threads.iter() {
+      // Without a starter message the thread is private unless its kind is given
+      let thread = project_chan
+        .create_private_thread(http, |thread| {
+          thread.name(thread_name).kind(ChannelType::PublicThread)
+        })
+        .await?;
+      resources.threads.push(thread.id);
+    }
+    Ok(())
+  }
+  .await;
+  if let Err(why) = result {
+    resources.delete(guild_id, http).await;
+    return Err(why);
+  }
+  Ok(resources)
+}
+
+/// The threads are deleted with the text channel
+pub async fn delete_resources(
+  project: &Project,
+  guild_id: GuildId,
+  http: &Http,
+) -> Result<(), SerenityError> {
+  if let Some(voice_channel_id) = project.voice_channel_id {
+    ChannelId(voice_channel_id as u64).delete(http).await?;
+  }
+  if let Some(role_id) = project.role_id {
+    guild_id.delete_role(http, role_id as u64).await?;
+  }
+  Ok(())
+}
+
+#[test]
+fn test_parse_template() {
+  use crate::core::arguments::Arguments;
+
+  let args: Vec<String> = vec![
+    "project-template",
+    "set",
+    "video",
+    "voice=yes",
+    "threads=brief, assets,,review",
+  ]
+  .into_iter()
+  .map(String::from)
+  .collect();
+  let mut arguments = Arguments::new(&args);
+  arguments.next_raw().unwrap();
+  arguments.next_raw().unwrap();
+  let template = parse_template(GuildId(1), "video", arguments.many().unwrap());
+  assert!(template.voice_channel);
+  assert_eq!(template.threads, vec!["brief", "assets", "review"]);
+
+  let args = [&args[..3], &[String::from("voice=maybe")]].concat();
+  let mut arguments = Arguments::new(&args);
+  arguments.next_raw().unwrap();
+  arguments.next_raw().unwrap();
+  let error = arguments.many::<TemplateOption>().unwrap_err();
+  assert_eq!(error.position, 3);
+}
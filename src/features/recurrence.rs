@@ -0,0 +1,348 @@
+//! Recurrence rules of the repeated events, the rule is stored as text in `events.recurrence`.
+//!
+//! Accepted rules, the leading `every` is optional:
+//! - `every day at 09:45`
+//! - `every weekday at 09:45`: monday to friday
+//! - `every monday at 09:45`
+//! - `every first monday of the month at 09:45`: first, second, third, fourth or last
+//! - `cron 45 9 * * 1-5`: minute hour day month weekday, with `*`, lists, ranges and steps
+use super::events::dateparse::resolve_local;
+use crate::core::arguments::FromArgument;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use std::{fmt::Display, str::FromStr};
+
+/// Cron rules are looked up to 4 years ahead so the 29th of february is found
+const MAX_DAYS_AHEAD: i64 = 366 * 4;
+
+const WEEKDAY_NAMES: [(&str, Weekday); 7] = [
+  ("monday", Weekday::Mon),
+  ("tuesday", Weekday::Tue),
+  ("wednesday", Weekday::Wed),
+  ("thursday", Weekday::Thu),
+  ("friday", Weekday::Fri),
+  ("saturday", Weekday::Sat),
+  ("sunday", Weekday::Sun),
+];
+const NTH_NAMES: [(&str, i8); 5] = [
+  ("first", 1),
+  ("second", 2),
+  ("third", 3),
+  ("fourth", 4),
+  ("last", -1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+  Daily(NaiveTime),
+  Weekdays(NaiveTime),
+  Weekly(Weekday, NaiveTime),
+  /// Nth weekday of the month, -1 being the last one
+  Monthly(i8, Weekday, NaiveTime),
+  Cron(Cron),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+  minutes: Vec<u32>,
+  hours: Vec<u32>,
+  days: Vec<u32>,
+  months: Vec<u32>,
+  /// 0 is sunday, like in cron
+  weekdays: Vec<u32>,
+  any_day: bool,
+  any_weekday: bool,
+  source: String,
+}
+
+impl Recurrence {
+  fn matches_date(&self, date: NaiveDate) -> bool {
+    match self {
+      Recurrence::Daily(_) => true,
+      Recurrence::Weekdays(_) => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+      Recurrence::Weekly(weekday, _) => date.weekday() == *weekday,
+      Recurrence::Monthly(nth, weekday, _) => {
+        date.weekday() == *weekday
+          && match nth {
+            -1 => (date + Duration::days(7)).month() != date.month(),
+            nth => (date.day0() / 7 + 1) as i8 == *nth,
+          }
+      }
+      Recurrence::Cron(cron) => cron.matches_date(date),
+    }
+  }
+
+  fn times(&self) -> Vec<NaiveTime> {
+    match self {
+      Recurrence::Daily(time)
+      | Recurrence::Weekdays(time)
+      | Recurrence::Weekly(_, time)
+      | Recurrence::Monthly(_, _, time) => vec![*time],
+      Recurrence::Cron(cron) => cron
+        .hours
+        .iter()
+        .flat_map(|hour| {
+          cron
+            .minutes
+            .iter()
+            .filter_map(move |minute| NaiveTime::from_hms_opt(*hour, *minute, 0))
+        })
+        .collect(),
+    }
+  }
+
+  /// Next occurrence strictly after `after`, computed in the timezone of `after`
+  pub fn next_after(&self, after: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let timezone = after.timezone();
+    let start = after.naive_local().date();
+    let times = self.times();
+    for day in 0..MAX_DAYS_AHEAD {
+      let date = start + Duration::days(day);
+      if !self.matches_date(date) {
+        continue;
+      }
+      for time in times.iter() {
+        match resolve_local(timezone, date.and_time(*time)) {
+          Some(occurrence) if occurrence > after => return Some(occurrence),
+          _ => {}
+        }
+      }
+    }
+    None
+  }
+}
+
+impl Cron {
+  fn matches_date(&self, date: NaiveDate) -> bool {
+    if !self.months.contains(&date.month()) {
+      return false;
+    }
+    let day = self.days.contains(&date.day());
+    let weekday = self
+      .weekdays
+      .contains(&date.weekday().num_days_from_sunday());
+    // Like cron, when both the day and the weekday are restricted either can match
+    match (self.any_day, self.any_weekday) {
+      (false, false) => day || weekday,
+      _ => day && weekday,
+    }
+  }
+}
+
+/// Parse a cron field: `*`, `5`, `1-5`, `*/15`, `1-10/2` or a list of them
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>, String> {
+  let invalid = || format!("invalid cron field {}", field);
+  let mut values = Vec::new();
+  for part in field.split(',') {
+    let (range, step) = match part.split_once('/') {
+      Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+      None => (part, 1),
+    };
+    let (start, end) = match range {
+      "*" => (min, max),
+      range => match range.split_once('-') {
+        Some((start, end)) => (
+          start.parse().map_err(|_| invalid())?,
+          end.parse().map_err(|_| invalid())?,
+        ),
+        None => {
+          let value = range.parse().map_err(|_| invalid())?;
+          (value, value)
+        }
+      },
+    };
+    if step == 0 || start < min || end > max || start > end {
+      return Err(invalid());
+    }
+    values.extend((start..=end).step_by(step as usize));
+  }
+  values.sort_unstable();
+  values.dedup();
+  Ok(values)
+}
+
+impl FromStr for Cron {
+  type Err = String;
+
+  fn from_str(source: &str) -> Result<Self, Self::Err> {
+    let fields: Vec<&str> = source.split_whitespace().collect();
+    if fields.len() != 5 {
+      return Err(String::from(
+        "a cron rule needs 5 fields: minute hour day month weekday",
+      ));
+    }
+    let mut weekdays = parse_cron_field(fields[4], 0, 7)?;
+    // 7 is also sunday
+    if weekdays.contains(&7) {
+      weekdays.retain(|weekday| *weekday != 7);
+      weekdays.insert(0, 0);
+      weekdays.dedup();
+    }
+    Ok(Cron {
+      minutes: parse_cron_field(fields[0], 0, 59)?,
+      hours: parse_cron_field(fields[1], 0, 23)?,
+      days: parse_cron_field(fields[2], 1, 31)?,
+      months: parse_cron_field(fields[3], 1, 12)?,
+      weekdays,
+      any_day: fields[2] == "*",
+      any_weekday: fields[4] == "*",
+      source: fields.join(" "),
+    })
+  }
+}
+
+/// `09:45`, `9h45` or `9h`
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+  NaiveTime::parse_from_str(time, "%H:%M")
+    .or_else(|_| NaiveTime::parse_from_str(time, "%Hh%M"))
+    .or_else(|_| NaiveTime::parse_from_str(&format!("{}00", time), "%Hh%M"))
+    .map_err(|_| format!("invalid time {}, expected HH:MM", time))
+}
+
+fn parse_weekday(weekday: &str) -> Option<Weekday> {
+  WEEKDAY_NAMES
+    .iter()
+    .find(|(name, _)| *name == weekday || name[..3] == *weekday)
+    .map(|(_, weekday)| *weekday)
+}
+
+impl FromStr for Recurrence {
+  type Err = String;
+
+  fn from_str(rule: &str) -> Result<Self, Self::Err> {
+    let rule = rule.trim().to_lowercase();
+    if let Some(cron) = rule.strip_prefix("cron ") {
+      return Ok(Recurrence::Cron(cron.parse()?));
+    }
+    let words: Vec<&str> = rule
+      .split_whitespace()
+      .filter(|word| !["every", "of", "the", "month", "at"].contains(word))
+      .collect();
+    let invalid = || format!("invalid recurrence {}", rule);
+    match words.as_slice() {
+      ["day", time] => Ok(Recurrence::Daily(parse_time(time)?)),
+      ["weekday", time] | ["weekdays", time] => Ok(Recurrence::Weekdays(parse_time(time)?)),
+      [weekday, time] => Ok(Recurrence::Weekly(
+        parse_weekday(weekday).ok_or_else(invalid)?,
+        parse_time(time)?,
+      )),
+      [nth, weekday, time] => Ok(Recurrence::Monthly(
+        NTH_NAMES
+          .iter()
+          .find(|(name, _)| name == nth)
+          .ok_or_else(invalid)?
+          .1,
+        parse_weekday(weekday).ok_or_else(invalid)?,
+        parse_time(time)?,
+      )),
+      _ => Err(invalid()),
+    }
+  }
+}
+
+impl FromArgument for Recurrence {
+  const EXPECTED: &'static str = "expected a recurrence: \"every weekday at 09:45\"";
+
+  fn from_argument(argument: &str) -> Result<Self, String> {
+    argument.parse()
+  }
+}
+
+impl Display for Recurrence {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let weekday_name = |weekday: &Weekday| WEEKDAY_NAMES[weekday.num_days_from_monday() as usize].0;
+    match self {
+      Recurrence::Daily(time) => write!(f, "every day at {}", time.format("%H:%M")),
+      Recurrence::Weekdays(time) => write!(f, "every weekday at {}", time.format("%H:%M")),
+      Recurrence::Weekly(weekday, time) => write!(
+        f,
+        "every {} at {}",
+        weekday_name(weekday),
+        time.format("%H:%M")
+      ),
+      Recurrence::Monthly(nth, weekday, time) => write!(
+        f,
+        "every {} {} of the month at {}",
+        NTH_NAMES
+          .iter()
+          .find(|(_, value)| value == nth)
+          .map_or("", |(name, _)| name),
+        weekday_name(weekday),
+        time.format("%H:%M")
+      ),
+      Recurrence::Cron(cron) => write!(f, "cron {}", cron.source),
+    }
+  }
+}
+
+#[test]
+fn test_recurrence_parse() {
+  let rules = [
+    "every day at 09:45",
+    "every weekday at 09:45",
+    "every monday at 10:00",
+    "every last friday of the month at 17:30",
+    "cron */15 9-18 * * 1-5",
+  ];
+  for rule in rules {
+    assert_eq!(rule.parse::<Recurrence>().unwrap().to_string(), rule);
+  }
+  assert_eq!(
+    "Every first Mon 9h".parse::<Recurrence>().unwrap(),
+    Recurrence::Monthly(1, Weekday::Mon, NaiveTime::from_hms(9, 0, 0))
+  );
+  assert!("every fifth monday at 10:00".parse::<Recurrence>().is_err());
+  assert!("cron 60 * * * *".parse::<Recurrence>().is_err());
+}
+
+#[test]
+fn test_recurrence_next_after() {
+  use chrono::TimeZone;
+  use chrono_tz::Europe::Paris;
+
+  let next =
+    |rule: &str, after: DateTime<Tz>| rule.parse::<Recurrence>().unwrap().next_after(after);
+  // Friday 28/10/2022 10:00
+  let friday = Paris.ymd(2022, 10, 28).and_hms(10, 0, 0);
+
+  assert_eq!(
+    next("every weekday at 09:45", friday),
+    Some(Paris.ymd(2022, 10, 31).and_hms(9, 45, 0))
+  );
+  assert_eq!(
+    next("every day at 10:30", friday),
+    Some(Paris.ymd(2022, 10, 28).and_hms(10, 30, 0))
+  );
+  assert_eq!(
+    next("every first monday at 09:00", friday),
+    Some(Paris.ymd(2022, 11, 7).and_hms(9, 0, 0))
+  );
+  assert_eq!(
+    next("every last friday at 09:00", friday),
+    Some(Paris.ymd(2022, 11, 25).and_hms(9, 0, 0))
+  );
+  assert_eq!(
+    next("cron 0 12 29 2 *", friday),
+    Some(Paris.ymd(2024, 2, 29).and_hms(12, 0, 0))
+  );
+  // The time stays 09:45 in Paris when changing to winter time on the 30th
+  let next_day = next(
+    "every day at 09:45",
+    Paris.ymd(2022, 10, 29).and_hms(10, 0, 0),
+  )
+  .unwrap();
+  assert_eq!(
+    next_day.naive_utc(),
+    NaiveDate::from_ymd(2022, 10, 30).and_hms(8, 45, 0)
+  );
+  // 02:30 doesn't exist on the 27th of march 2022
+  assert_eq!(
+    next(
+      "every day at 02:30",
+      Paris.ymd(2022, 3, 27).and_hms(0, 0, 0)
+    )
+    .unwrap()
+    .naive_utc(),
+    NaiveDate::from_ymd(2022, 3, 27).and_hms(1, 30, 0)
+  );
+}
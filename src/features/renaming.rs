@@ -6,24 +6,32 @@ use log::error;
 use procedural_macros::command;
 use serenity::model::id::UserId;
 
-#[command]
+#[command(
+  name = "rename",
+  usage = "@BOT rename <@user> <new nickname> [<guild>]",
+  min_args = 2,
+  max_args = 3,
+  permission = User
+)]
 pub async fn rename(params: CallBackParams) -> CallbackReturn {
-  if params.args[2].len() > 32 {
+  let mut arguments = params.arguments();
+  let targeted_user_id: UserId = arguments.next()?;
+  let nickname: String = arguments.next()?;
+  let guild_name: Option<String> = arguments.optional()?;
+  if nickname.len() > 32 {
     return Ok(Some("The new nickname is too long.".to_string()));
   }
   let http = &params.context.http;
   let channel_id = params.message.channel_id;
-  let guild = match parse::get_guild(channel_id, params.context, params.args.get(3)).await {
+  let guild = match parse::get_guild(channel_id, params.context, guild_name.as_ref()).await {
     Ok(guild) => guild,
     Err(error) => return Ok(Some(error)),
   };
-  let (targeted_user_id, _) =
-    parse::discord_str_to_id(&params.args[1], Some(parse::DiscordIds::User))?;
-  let member = guild.member(http, UserId(targeted_user_id)).await;
+  let member = guild.member(http, targeted_user_id).await;
   match member {
     Ok(member) => {
       member
-        .edit(http, |member| member.nickname(&params.args[2]))
+        .edit(http, |member| member.nickname(nickname))
         .await?;
       Ok(Some(String::from(":ok:")))
     }
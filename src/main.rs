@@ -38,7 +38,6 @@ pub mod macros;
 #[macro_use]
 extern crate rocket;
 
-mod constants;
 mod core;
 mod database;
 mod features;